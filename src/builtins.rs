@@ -0,0 +1,89 @@
+use super::*;
+
+/// Variables available to every template without being declared in its
+/// frontmatter, namely `sk` and `env`.
+///
+/// Each is built on first use and then kept, since reading the environment
+/// and the git config is too costly to repeat for every placeholder.
+#[derive(Debug, Default)]
+pub(crate) struct Builtins {
+  env: OnceCell<Value>,
+  sk: OnceCell<Value>,
+}
+
+impl Builtins {
+  /// Collects the environment variables, replacing anything that isn't valid
+  /// UTF-8 in their names and values.
+  fn env() -> Value {
+    Value::Mapping(
+      env::vars_os()
+        .map(|(key, value)| {
+          (
+            key.to_string_lossy().into_owned().into(),
+            value.to_string_lossy().into_owned().into(),
+          )
+        })
+        .collect(),
+    )
+  }
+
+  /// Returns the built-in variable called `name`, if there is one, where
+  /// `file` is the path the template is being applied to.
+  pub(crate) fn get(&self, name: &str, file: Option<&Path>) -> Option<&Value> {
+    match name {
+      "env" => Some(self.env.get_or_init(Self::env)),
+      "sk" => Some(self.sk.get_or_init(|| Self::sk(file))),
+      _ => None,
+    }
+  }
+
+  /// Reads `key` from the git config, if git is installed and it is set.
+  fn git_config(key: &str) -> Option<Value> {
+    let output = Command::new("git")
+      .args(["config", "--get", key])
+      .output()
+      .ok()
+      .filter(|output| output.status.success())?;
+
+    let value = String::from_utf8(output.stdout).ok()?;
+
+    let value = value.trim();
+
+    (!value.is_empty()).then(|| Value::String(value.to_owned()))
+  }
+
+  fn sk(file: Option<&Path>) -> Value {
+    let mut sk = serde_yaml::Mapping::new();
+
+    if let Some(file) = file {
+      sk.insert("file".into(), file.to_string_lossy().into());
+    }
+
+    let now = Local::now();
+
+    sk.insert("date".into(), now.format("%Y-%m-%d").to_string().into());
+    sk.insert("year".into(), now.year().into());
+
+    if let Ok(dir) = env::current_dir() {
+      if let Some(dir_name) = dir.file_name() {
+        sk.insert("dir_name".into(), dir_name.to_string_lossy().into());
+      }
+
+      sk.insert("dir".into(), dir.to_string_lossy().into());
+    }
+
+    let mut git = serde_yaml::Mapping::new();
+
+    for (name, key) in
+      [("user_email", "user.email"), ("user_name", "user.name")]
+    {
+      if let Some(value) = Self::git_config(key) {
+        git.insert(name.into(), value);
+      }
+    }
+
+    sk.insert("git".into(), Value::Mapping(git));
+
+    Value::Mapping(sk)
+  }
+}
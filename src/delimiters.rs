@@ -0,0 +1,67 @@
+use super::*;
+
+/// The strings that open and close tags in a template body, `{%` and `%}`
+/// unless overridden by the `delimiters` effect variable.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Delimiters {
+  pub(crate) close: String,
+  pub(crate) open: String,
+}
+
+impl Default for Delimiters {
+  fn default() -> Self {
+    Self {
+      close: "%}".to_owned(),
+      open: "{%".to_owned(),
+    }
+  }
+}
+
+impl TryFrom<&Value> for Delimiters {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &Value) -> Result<Self> {
+    let invalid = || {
+      anyhow!(
+        "`delimiters` must be a sequence of two distinct, non-empty strings"
+      )
+    };
+
+    let Some([Value::String(open), Value::String(close)]) =
+      value.as_sequence().map(Vec::as_slice)
+    else {
+      return Err(invalid());
+    };
+
+    if open.trim().is_empty() || close.trim().is_empty() || open == close {
+      return Err(invalid());
+    }
+
+    Ok(Self {
+      close: close.trim().to_owned(),
+      open: open.trim().to_owned(),
+    })
+  }
+}
+
+impl Delimiters {
+  /// Reads the delimiters set on a `delimiters:` line of `yaml`, for
+  /// frontmatter that doesn't parse as a whole, or the default ones.
+  pub(crate) fn declared(yaml: &str) -> Self {
+    yaml
+      .lines()
+      .filter(|line| line.starts_with("delimiters:"))
+      .find_map(|line| {
+        serde_yaml::from_str::<IndexMap<String, Value>>(line)
+          .ok()?
+          .get("delimiters")
+          .and_then(|value| Self::try_from(value).ok())
+      })
+      .unwrap_or_default()
+  }
+
+  /// Formats `content` as a tag, used when referring to tags in errors.
+  pub(crate) fn tag(&self, content: &str) -> String {
+    format!("{} {content} {}", self.open, self.close)
+  }
+}
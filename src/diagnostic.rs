@@ -0,0 +1,246 @@
+use super::*;
+
+/// An error at a span of a template, reported with its path, line and column,
+/// the offending line with a caret under the span, and an optional hint.
+///
+/// Diagnostics are created with a byte offset into the source at hand, such
+/// as a template body, and later located in the template's content, which
+/// fills in everything shown after the message.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Diagnostic {
+  column: usize,
+  hint: Option<String>,
+  length: usize,
+  line: usize,
+  message: String,
+  offset: usize,
+  path: Option<PathBuf>,
+  snippet: String,
+}
+
+impl Diagnostic {
+  pub(crate) fn hint(self, hint: impl Into<String>) -> Self {
+    Self {
+      hint: Some(hint.into()),
+      ..self
+    }
+  }
+
+  /// Locates the diagnostic in the template at `path`, whose `content`
+  /// contains the diagnostic's source at byte offset `base`.
+  pub(crate) fn locate(self, path: &Path, content: &str, base: usize) -> Self {
+    let mut offset = (base + self.offset).min(content.len());
+
+    while !content.is_char_boundary(offset) {
+      offset -= 1;
+    }
+
+    let start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+
+    let end = content[offset..]
+      .find('\n')
+      .map_or(content.len(), |i| offset + i);
+
+    let snippet = content[start..end].trim_end_matches('\r');
+
+    let before = snippet.get(..offset - start).unwrap_or(snippet);
+
+    Self {
+      column: before.chars().count() + 1,
+      length: snippet[before.len()..]
+        .chars()
+        .take(self.length)
+        .count()
+        .max(1),
+      line: content[..offset].matches('\n').count() + 1,
+      path: Some(path.to_owned()),
+      snippet: snippet.to_owned(),
+      ..self
+    }
+  }
+
+  /// Locates `error` like `locate` if it is a diagnostic that hasn't been
+  /// located yet, and returns it unchanged otherwise.
+  pub(crate) fn locate_error(
+    error: anyhow::Error,
+    path: &Path,
+    content: &str,
+    base: usize,
+  ) -> anyhow::Error {
+    match error.downcast::<Diagnostic>() {
+      Ok(diagnostic) if diagnostic.path.is_none() => {
+        diagnostic.locate(path, content, base).into()
+      }
+      Ok(diagnostic) => diagnostic.into(),
+      Err(error) => error,
+    }
+  }
+
+  pub(crate) fn new(
+    message: impl Into<String>,
+    offset: usize,
+    length: usize,
+  ) -> Self {
+    Self {
+      column: 0,
+      hint: None,
+      length,
+      line: 0,
+      message: message.into(),
+      offset,
+      path: None,
+      snippet: String::new(),
+    }
+  }
+
+  /// Converts a YAML error in `yaml`, a template's frontmatter, into a
+  /// diagnostic at the error's location, with a hint for unquoted
+  /// placeholders written with `delimiters`.
+  pub(crate) fn yaml(
+    error: &serde_yaml::Error,
+    yaml: &str,
+    delimiters: &Delimiters,
+  ) -> Self {
+    let message = error.to_string();
+
+    let Some(location) = error.location() else {
+      return Self::new(message, 0, 0);
+    };
+
+    let line = yaml
+      .split_inclusive('\n')
+      .nth(location.line().saturating_sub(1))
+      .unwrap_or_default();
+
+    let start = yaml
+      .split_inclusive('\n')
+      .take(location.line().saturating_sub(1))
+      .map(str::len)
+      .sum::<usize>();
+
+    let column = line
+      .char_indices()
+      .nth(location.column().saturating_sub(1))
+      .map_or(line.trim_end().len(), |(i, _)| i);
+
+    let message = message
+      .rsplit_once(" at line ")
+      .map_or(message.as_str(), |(message, _)| message)
+      .to_owned();
+
+    let diagnostic = Self::new(message, start + column, 1);
+
+    if line.contains(&delimiters.open) {
+      diagnostic.hint(format!(
+        "quote values containing placeholders, as in `name: \"{}\"`",
+        delimiters.tag("project")
+      ))
+    } else if line.matches(": ").count() > 1 {
+      diagnostic.hint("quote values containing `: `, as in `title: \"a: b\"`")
+    } else {
+      diagnostic
+    }
+  }
+}
+
+impl Display for Diagnostic {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "{}", self.message)?;
+
+    let mut gutter = String::new();
+
+    if let Some(path) = &self.path {
+      gutter = " ".repeat(self.line.to_string().len());
+
+      let padding = self
+        .snippet
+        .chars()
+        .take(self.column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+
+      write!(
+        f,
+        "\n{gutter} --> {}:{}:{}\n{gutter} |\n{} | {}\n{gutter} | {padding}{}",
+        path.display(),
+        self.line,
+        self.column,
+        self.line,
+        self.snippet,
+        "^".repeat(self.length)
+      )?;
+    }
+
+    if let Some(hint) = &self.hint {
+      write!(f, "\n{gutter} = hint: {hint}")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl std::error::Error for Diagnostic {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn display() {
+    let content = "---\na: 1\n---\nline\n\tx {% nmae %} y\n";
+
+    assert_eq!(
+      Diagnostic::new("undefined variable `nmae`", 8, 10)
+        .hint("check the spelling")
+        .locate(Path::new("a.skeleton"), content, 13)
+        .to_string(),
+      "undefined variable `nmae`\n  \
+       --> a.skeleton:5:4\n  \
+       |\n\
+       5 | \tx {% nmae %} y\n  \
+       | \t  ^^^^^^^^^^\n  \
+       = hint: check the spelling"
+    );
+
+    assert_eq!(
+      Diagnostic::new("unexpected end", 100, 1)
+        .locate(Path::new("a.skeleton"), content, 0)
+        .to_string(),
+      "unexpected end\n  --> a.skeleton:6:1\n  |\n6 | \n  | ^"
+    );
+
+    assert_eq!(
+      Diagnostic::new("unlocated", 0, 1)
+        .hint("a hint")
+        .to_string(),
+      "unlocated\n = hint: a hint"
+    );
+  }
+
+  #[test]
+  fn yaml() {
+    let yaml = "a: 1\nb: {% c %}\n";
+
+    let error = serde_yaml::from_str::<Value>(yaml).unwrap_err();
+
+    let diagnostic = Diagnostic::yaml(&error, yaml, &Delimiters::default())
+      .locate(Path::new("y.skeleton"), yaml, 0);
+
+    assert_eq!((diagnostic.line, diagnostic.column), (2, 5));
+
+    assert!(diagnostic.hint.unwrap().starts_with("quote values"));
+
+    assert!(!diagnostic.message.contains(" at line "));
+
+    let yaml = "delimiters: [\"<<\", \">>\"]\nb: << c >>: d\n";
+
+    let error = serde_yaml::from_str::<Value>(yaml).unwrap_err();
+
+    assert_eq!(
+      Diagnostic::yaml(&error, yaml, &Delimiters::declared(yaml))
+        .hint
+        .unwrap(),
+      "quote values containing placeholders, as in `name: \"<< project >>\"`"
+    );
+  }
+}
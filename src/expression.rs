@@ -0,0 +1,225 @@
+use super::*;
+
+/// A variable reference followed by zero or more filters, as found inside
+/// placeholders, conditions and loops, e.g. `name | replace("-", "_") | upper`.
+///
+/// The reference can reach into nested mappings and sequences, as in
+/// `db.host` or `authors[0].email`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Expression {
+  pub(crate) filters: Vec<Filter>,
+  pub(crate) name: String,
+  pub(crate) path: Vec<Segment>,
+}
+
+impl Expression {
+  pub(crate) fn evaluate(
+    &self,
+    value: Option<&Value>,
+  ) -> Result<Option<Value>> {
+    self
+      .filters
+      .iter()
+      .try_fold(value.cloned(), |value, filter| filter.apply(value))
+  }
+
+  pub(crate) fn parse_argument(argument: &str) -> Result<Value> {
+    let argument = argument.trim();
+
+    let quote = argument.chars().next().filter(|c| matches!(c, '"' | '\''));
+
+    let Some(quote) = quote else {
+      return serde_yaml::from_str(argument)
+        .map_err(|_| anyhow!("invalid filter argument `{argument}`"));
+    };
+
+    if argument.len() < 2 || !argument.ends_with(quote) {
+      bail!("unterminated string `{argument}`");
+    }
+
+    let mut string = String::new();
+
+    let mut chars = argument[1..argument.len() - 1].chars();
+
+    while let Some(c) = chars.next() {
+      if c != '\\' {
+        string.push(c);
+        continue;
+      }
+
+      string.push(match chars.next() {
+        Some('n') => '\n',
+        Some('t') => '\t',
+        Some(c) => c,
+        None => bail!("unterminated escape in `{argument}`"),
+      });
+    }
+
+    Ok(Value::String(string))
+  }
+
+  fn parse_filter(filter: &str) -> Result<Filter> {
+    let filter = filter.trim();
+
+    let Some((name, arguments)) = filter.split_once('(') else {
+      return Filter::new(filter, Vec::new());
+    };
+
+    let arguments = arguments
+      .trim_end()
+      .strip_suffix(')')
+      .ok_or_else(|| anyhow!("missing `)` in filter `{filter}`"))?;
+
+    let arguments = if arguments.trim().is_empty() {
+      Vec::new()
+    } else {
+      Self::split(arguments, ',')?
+        .into_iter()
+        .map(Self::parse_argument)
+        .collect::<Result<Vec<Value>>>()?
+    };
+
+    Filter::new(name.trim(), arguments)
+  }
+
+  /// Splits `source` on `separator`, ignoring separators within quotes.
+  fn split(source: &str, separator: char) -> Result<Vec<&str>> {
+    let mut parts = Vec::new();
+
+    let mut quote = None;
+
+    let mut start = 0;
+
+    let mut escaped = false;
+
+    for (index, c) in source.char_indices() {
+      match (quote, c) {
+        _ if escaped => escaped = false,
+        (Some(_), '\\') => escaped = true,
+        (Some(q), c) if q == c => quote = None,
+        (None, '"' | '\'') => quote = Some(c),
+        (None, c) if c == separator => {
+          parts.push(&source[start..index]);
+          start = index + c.len_utf8();
+        }
+        _ => {}
+      }
+    }
+
+    if quote.is_some() {
+      bail!("unterminated string in `{source}`");
+    }
+
+    parts.push(&source[start..]);
+
+    Ok(parts)
+  }
+
+  /// The variable reference as written, e.g. `authors[0].email`.
+  pub(crate) fn variable(&self) -> String {
+    Segment::display(&self.name, &self.path)
+  }
+}
+
+impl FromStr for Expression {
+  type Err = anyhow::Error;
+
+  fn from_str(source: &str) -> Result<Self> {
+    let mut parts = Self::split(source, '|')?.into_iter();
+
+    let name = parts.next().unwrap_or_default().trim();
+
+    if name.is_empty() {
+      bail!("expected a variable name in `{}`", source.trim());
+    }
+
+    let (name, path) = Segment::parse(name)?;
+
+    Ok(Self {
+      filters: parts
+        .map(Self::parse_filter)
+        .collect::<Result<Vec<Filter>>>()?,
+      name,
+      path,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn plain() {
+    assert_eq!(
+      "name".parse::<Expression>().unwrap(),
+      Expression {
+        filters: Vec::new(),
+        name: "name".to_owned(),
+        path: Vec::new(),
+      }
+    );
+  }
+
+  #[test]
+  fn nested() {
+    let expression = "db.hosts[1] | upper".parse::<Expression>().unwrap();
+
+    assert_eq!(expression.name, "db");
+
+    assert_eq!(
+      expression.path,
+      vec![Segment::Key("hosts".to_owned()), Segment::Index(1)]
+    );
+
+    assert_eq!(expression.variable(), "db.hosts[1]");
+  }
+
+  #[test]
+  fn filters() {
+    assert_eq!(
+      r#"name | replace("|", ", ") | default('a\'b') | upper"#
+        .parse::<Expression>()
+        .unwrap(),
+      Expression {
+        filters: vec![
+          Filter::Replace("|".to_owned(), ", ".to_owned()),
+          Filter::Default(Value::String("a'b".to_owned())),
+          Filter::Upper,
+        ],
+        name: "name".to_owned(),
+        path: Vec::new(),
+      }
+    );
+  }
+
+  #[test]
+  fn evaluate() {
+    let expression = "name | snake_case | upper".parse::<Expression>().unwrap();
+
+    assert_eq!(
+      expression
+        .evaluate(Some(&Value::String("fooBar".to_owned())))
+        .unwrap(),
+      Some(Value::String("FOO_BAR".to_owned()))
+    );
+
+    assert_eq!(expression.evaluate(None).unwrap(), None);
+  }
+
+  #[test]
+  fn invalid() {
+    assert_eq!(
+      "| upper".parse::<Expression>().unwrap_err().to_string(),
+      "expected a variable name in `| upper`"
+    );
+
+    assert_eq!(
+      "name | default(\"x)"
+        .parse::<Expression>()
+        .unwrap_err()
+        .to_string(),
+      "unterminated string in `name | default(\"x)`"
+    );
+  }
+}
@@ -0,0 +1,228 @@
+use super::*;
+
+/// A transformation applied to a placeholder value, written after a pipe as in
+/// `{% name | snake_case %}`.
+///
+/// New filters are added by extending this enum, `Filter::new`, `Filter::name`
+/// and `Filter::transform`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Filter {
+  Default(Value),
+  Join(String),
+  Json,
+  KebabCase,
+  Lower,
+  PascalCase,
+  Replace(String, String),
+  SnakeCase,
+  Toml,
+  Trim,
+  Upper,
+  Yaml,
+}
+
+impl Filter {
+  pub(crate) fn apply(&self, value: Option<Value>) -> Result<Option<Value>> {
+    match (self, value) {
+      (Filter::Default(default), None | Some(Value::Null)) => {
+        Ok(Some(default.clone()))
+      }
+      (_, None) => Ok(None),
+      (Filter::Default(_), Some(value)) => Ok(Some(value)),
+      (Filter::Join(separator), Some(value)) => Ok(Some(Value::String(
+        value
+          .as_sequence()
+          .and_then(|sequence| {
+            sequence
+              .iter()
+              .map(ValueExt::scalar)
+              .collect::<Option<Vec<String>>>()
+          })
+          .ok_or_else(|| {
+            anyhow!("filter `join` expects a sequence of scalars")
+          })?
+          .join(separator),
+      ))),
+      (Filter::Json, Some(value)) => {
+        Ok(Some(Value::String(serde_json::to_string(&value)?)))
+      }
+      (Filter::Toml, Some(value)) => {
+        let toml = match toml::Value::try_from(&value)? {
+          toml::Value::Table(table) => toml::to_string(&table)?,
+          value => value.to_string(),
+        };
+
+        Ok(Some(Value::String(toml.trim_end().to_owned())))
+      }
+      (Filter::Yaml, Some(value)) => Ok(Some(Value::String(
+        serde_yaml::to_string(&value)?.trim_end().to_owned(),
+      ))),
+      (_, Some(value)) => {
+        let string = value.scalar().ok_or_else(|| {
+          anyhow!("filter `{}` expects a scalar value", self.name())
+        })?;
+
+        Ok(Some(Value::String(self.transform(&string))))
+      }
+    }
+  }
+
+  fn name(&self) -> &'static str {
+    match self {
+      Filter::Default(_) => "default",
+      Filter::Join(_) => "join",
+      Filter::Json => "json",
+      Filter::KebabCase => "kebab_case",
+      Filter::Lower => "lower",
+      Filter::PascalCase => "pascal_case",
+      Filter::Replace(..) => "replace",
+      Filter::SnakeCase => "snake_case",
+      Filter::Toml => "toml",
+      Filter::Trim => "trim",
+      Filter::Upper => "upper",
+      Filter::Yaml => "yaml",
+    }
+  }
+
+  pub(crate) fn new(name: &str, arguments: Vec<Value>) -> Result<Self> {
+    let filter = match (name, arguments.as_slice()) {
+      ("default", [default]) => Filter::Default(default.clone()),
+      ("join", [separator]) => {
+        Filter::Join(separator.scalar().unwrap_or_default())
+      }
+      ("json", []) => Filter::Json,
+      ("kebab_case", []) => Filter::KebabCase,
+      ("lower", []) => Filter::Lower,
+      ("pascal_case", []) => Filter::PascalCase,
+      ("replace", [from, to]) => Filter::Replace(
+        from.scalar().unwrap_or_default(),
+        to.scalar().unwrap_or_default(),
+      ),
+      ("snake_case", []) => Filter::SnakeCase,
+      ("toml", []) => Filter::Toml,
+      ("trim", []) => Filter::Trim,
+      ("upper", []) => Filter::Upper,
+      ("yaml", []) => Filter::Yaml,
+      (
+        "default" | "join" | "json" | "kebab_case" | "lower" | "pascal_case"
+        | "replace" | "snake_case" | "toml" | "trim" | "upper" | "yaml",
+        _,
+      ) => bail!(
+        "filter `{name}` does not take {} argument(s)",
+        arguments.len()
+      ),
+      _ => bail!("unknown filter `{name}`"),
+    };
+
+    Ok(filter)
+  }
+
+  fn transform(&self, string: &str) -> String {
+    match self {
+      Filter::Default(_)
+      | Filter::Join(_)
+      | Filter::Json
+      | Filter::Toml
+      | Filter::Yaml => string.to_owned(),
+      Filter::KebabCase => string.to_kebab_case(),
+      Filter::Lower => string.to_lowercase(),
+      Filter::PascalCase => string.to_pascal_case(),
+      Filter::Replace(from, to) => string.replace(from, to),
+      Filter::SnakeCase => string.to_snake_case(),
+      Filter::Trim => string.trim().to_owned(),
+      Filter::Upper => string.to_uppercase(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn apply(filter: Filter, value: &str) -> Value {
+    filter
+      .apply(Some(Value::String(value.to_owned())))
+      .unwrap()
+      .unwrap()
+  }
+
+  #[test]
+  fn case_conversion() {
+    assert_eq!(apply(Filter::SnakeCase, "Hello World"), "hello_world");
+    assert_eq!(apply(Filter::KebabCase, "helloWorld"), "hello-world");
+    assert_eq!(apply(Filter::PascalCase, "hello-world"), "HelloWorld");
+    assert_eq!(apply(Filter::Upper, "hello"), "HELLO");
+    assert_eq!(apply(Filter::Lower, "HELLO"), "hello");
+  }
+
+  #[test]
+  fn default() {
+    let filter = Filter::Default(Value::String("x".to_owned()));
+
+    assert_eq!(filter.apply(None).unwrap(), Some(Value::from("x")));
+    assert_eq!(
+      filter.apply(Some(Value::Null)).unwrap(),
+      Some(Value::from("x"))
+    );
+    assert_eq!(
+      filter.apply(Some(Value::from("y"))).unwrap(),
+      Some(Value::from("y"))
+    );
+  }
+
+  #[test]
+  fn json() {
+    assert_eq!(
+      Filter::Json
+        .apply(Some(serde_yaml::from_str("[a, 1, true]").unwrap()))
+        .unwrap(),
+      Some(Value::from("[\"a\",1,true]"))
+    );
+  }
+
+  #[test]
+  fn formats() {
+    let value =
+      serde_yaml::from_str::<Value>("{name: a, tags: [b, c]}").unwrap();
+
+    assert_eq!(
+      Filter::Yaml.apply(Some(value.clone())).unwrap(),
+      Some(Value::from("name: a\ntags:\n- b\n- c"))
+    );
+
+    assert_eq!(
+      Filter::Toml.apply(Some(value.clone())).unwrap(),
+      Some(Value::from("name = \"a\"\ntags = [\"b\", \"c\"]"))
+    );
+
+    assert_eq!(
+      Filter::Join(", ".to_owned())
+        .apply(Some(value["tags"].clone()))
+        .unwrap(),
+      Some(Value::from("b, c"))
+    );
+
+    assert_eq!(
+      Filter::Join(", ".to_owned())
+        .apply(Some(value))
+        .unwrap_err()
+        .to_string(),
+      "filter `join` expects a sequence of scalars"
+    );
+  }
+
+  #[test]
+  fn invalid_filters() {
+    assert_eq!(
+      Filter::new("shout", Vec::new()).unwrap_err().to_string(),
+      "unknown filter `shout`"
+    );
+
+    assert_eq!(
+      Filter::new("replace", vec![Value::from("a")])
+        .unwrap_err()
+        .to_string(),
+      "filter `replace` does not take 1 argument(s)"
+    );
+  }
+}
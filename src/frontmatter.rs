@@ -0,0 +1,152 @@
+use super::*;
+
+/// A template's content split into its frontmatter and body, along the `---`
+/// lines around the frontmatter.
+///
+/// The delimiters must sit on lines of their own, so `---` within a YAML
+/// block scalar or followed by other text doesn't end the frontmatter, and the
+/// body is kept exactly as written.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Frontmatter<'src> {
+  pub(crate) body: &'src str,
+  pub(crate) offset: usize,
+  pub(crate) yaml: &'src str,
+  pub(crate) yaml_offset: usize,
+}
+
+impl<'src> Frontmatter<'src> {
+  const BOM: char = '\u{feff}';
+
+  pub(crate) const DELIMITER: &'static str = "---";
+
+  /// Whether `line`, including its line ending, is a delimiter line.
+  fn is_delimiter(line: &str) -> bool {
+    line
+      .trim_end_matches(['\n', '\r'])
+      .trim_end_matches([' ', '\t'])
+      == Self::DELIMITER
+  }
+
+  pub(crate) fn parse(content: &'src str) -> Result<Self> {
+    let mut position = if content.starts_with(Self::BOM) {
+      Self::BOM.len_utf8()
+    } else {
+      0
+    };
+
+    let mut lines = content[position..].split_inclusive('\n');
+
+    let first = lines.next().unwrap_or_default();
+
+    if !Self::is_delimiter(first) {
+      return Err(
+        Diagnostic::new(
+          format!(
+            "template must start with `{}` to specify its frontmatter",
+            Self::DELIMITER
+          ),
+          position,
+          first.trim_end().len(),
+        )
+        .hint(format!(
+          "write the frontmatter between two `{}` lines at the top of the \
+           template",
+          Self::DELIMITER
+        ))
+        .into(),
+      );
+    }
+
+    let opening = position;
+
+    position += first.len();
+
+    let start = position;
+
+    for line in lines {
+      if Self::is_delimiter(line) {
+        return Ok(Self {
+          body: &content[position + line.len()..],
+          offset: position + line.len(),
+          yaml: &content[start..position],
+          yaml_offset: start,
+        });
+      }
+
+      position += line.len();
+    }
+
+    Err(
+      Diagnostic::new(
+        format!(
+          "template must contain a frontmatter ending with `{}`",
+          Self::DELIMITER
+        ),
+        opening,
+        Self::DELIMITER.len(),
+      )
+      .hint(format!(
+        "end the frontmatter with a `{}` line of its own",
+        Self::DELIMITER
+      ))
+      .into(),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn body_is_kept_as_written() {
+    assert_eq!(
+      Frontmatter::parse("---\na: 1\n---\n\n  indented\t\n\n").unwrap(),
+      Frontmatter {
+        body: "\n  indented\t\n\n",
+        offset: 13,
+        yaml: "a: 1\n",
+        yaml_offset: 4,
+      }
+    );
+  }
+
+  #[test]
+  fn crlf_and_bom() {
+    assert_eq!(
+      Frontmatter::parse("\u{feff}---\r\na: 1\r\n--- \r\nbody\r\n").unwrap(),
+      Frontmatter {
+        body: "body\r\n",
+        offset: 20,
+        yaml: "a: 1\r\n",
+        yaml_offset: 8,
+      }
+    );
+  }
+
+  #[test]
+  fn delimiter_lines() {
+    assert_eq!(
+      Frontmatter::parse("---\na: |\n  ---\nb: ---x\n----\n---\nbody")
+        .unwrap()
+        .yaml,
+      "a: |\n  ---\nb: ---x\n----\n"
+    );
+
+    assert_eq!(
+      Frontmatter::parse("--- a: 1\n---\nbody")
+        .unwrap_err()
+        .to_string(),
+      "template must start with `---` to specify its frontmatter\n = hint: \
+       write the frontmatter between two `---` lines at the top of the template"
+    );
+
+    assert_eq!(
+      Frontmatter::parse("---\na: 1\n---body")
+        .unwrap_err()
+        .to_string(),
+      "template must contain a frontmatter ending with `---`\n = hint: end \
+       the frontmatter with a `---` line of its own"
+    );
+  }
+}
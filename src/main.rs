@@ -1,36 +1,84 @@
 use {
   crate::{
     arguments::Arguments,
+    builtins::Builtins,
+    delimiters::Delimiters,
+    diagnostic::Diagnostic,
+    expression::Expression,
+    filter::Filter,
+    frontmatter::Frontmatter,
+    node::Node,
+    overrides::Overrides,
     path_ext::PathExt,
+    renderer::Renderer,
+    schema::Schema,
     search::Search,
+    segment::Segment,
     store::{Store, TEMPLATE_EXTENSION},
     subcommand::Subcommand,
     template::Template,
+    template_kind::TemplateKind,
+    template_parser::TemplateParser,
+    value_ext::ValueExt,
   },
   anyhow::{anyhow, bail, Context},
+  chrono::{Datelike, Local},
   clap::Parser,
   colored::*,
-  dialoguer::{theme::ColorfulTheme, Confirm, Input},
+  dialoguer::{
+    theme::{ColorfulTheme, Theme},
+    Confirm, Input, MultiSelect, Select,
+  },
+  heck::{ToKebabCase, ToPascalCase, ToSnakeCase},
+  indexmap::IndexMap,
   indoc::indoc,
+  regex::Regex,
   serde_yaml::Value,
   skim::prelude::*,
   std::{
-    collections::HashMap, env, fs, path::PathBuf, process, process::Command,
+    cell::OnceCell,
+    collections::HashMap,
+    env,
+    ffi::{OsStr, OsString},
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    process::Command,
+    rc::Rc,
+    str::FromStr,
     sync::Arc,
   },
   tempdir::TempDir,
-  walkdir::WalkDir,
+  walkdir::{DirEntry, WalkDir},
 };
 
 #[cfg(test)]
 use crate::subcommand::DEFAULT_TEMPLATE;
 
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
 mod arguments;
+mod builtins;
+mod delimiters;
+mod diagnostic;
+mod expression;
+mod filter;
+mod frontmatter;
+mod node;
+mod overrides;
 mod path_ext;
+mod renderer;
+mod schema;
 mod search;
+mod segment;
 mod store;
 mod subcommand;
 mod template;
+mod template_kind;
+mod template_parser;
+mod value_ext;
 
 type Result<T = (), E = anyhow::Error> = std::result::Result<T, E>;
 
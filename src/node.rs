@@ -0,0 +1,81 @@
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+  Block {
+    body: Vec<Node>,
+    name: String,
+    offset: usize,
+  },
+  Conditional {
+    alternative: Vec<Node>,
+    condition: Expression,
+    consequent: Vec<Node>,
+    offset: usize,
+  },
+  Extends {
+    name: String,
+    offset: usize,
+  },
+  Include {
+    name: String,
+    offset: usize,
+  },
+  Loop {
+    body: Vec<Node>,
+    iterable: Expression,
+    key: Option<String>,
+    offset: usize,
+    value: String,
+  },
+  Text(String),
+  Variable {
+    expression: Expression,
+    offset: usize,
+    source: String,
+  },
+}
+
+impl Node {
+  /// Returns the variable references in `nodes`, leaving out those to
+  /// variables bound by loops.
+  pub(crate) fn references(nodes: &[Node]) -> Vec<&Expression> {
+    let mut references = Vec::new();
+
+    for node in nodes {
+      match node {
+        Node::Block { body, .. } => references.extend(Self::references(body)),
+        Node::Conditional {
+          alternative,
+          condition,
+          consequent,
+          ..
+        } => {
+          references.push(condition);
+          references.extend(Self::references(consequent));
+          references.extend(Self::references(alternative));
+        }
+        Node::Loop {
+          body,
+          iterable,
+          key,
+          value,
+          ..
+        } => {
+          references.push(iterable);
+          references.extend(Self::references(body).into_iter().filter(
+            |reference| {
+              reference.name != *value && Some(&reference.name) != key.as_ref()
+            },
+          ));
+        }
+        Node::Variable { expression, .. } => {
+          references.push(expression);
+        }
+        Node::Extends { .. } | Node::Include { .. } | Node::Text(_) => {}
+      }
+    }
+
+    references
+  }
+}
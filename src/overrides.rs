@@ -0,0 +1,357 @@
+use super::*;
+
+/// Values for variables given on the command line, in files or in the
+/// environment, which take the place of those in a template's frontmatter.
+///
+/// Names can reach into nested variables, as in `db.host`, and later values
+/// take precedence over earlier ones.
+#[derive(Debug, Default)]
+pub(crate) struct Overrides {
+  values: IndexMap<String, Override>,
+}
+
+#[derive(Debug, Clone)]
+enum Override {
+  /// A string from the command line or the environment, converted to the
+  /// type of the variable it overrides when applied.
+  Raw(String),
+  /// A value from a YAML or JSON file.
+  Typed(Value),
+}
+
+impl Overrides {
+  const ENV_PREFIX: &'static str = "SK_VAR_";
+
+  /// Applies the overrides to `template`, returning the names of the free
+  /// variables they set.
+  ///
+  /// Only the free variables the template declares are overridden, so that
+  /// values meant for other templates, or for effect variables such as
+  /// `command`, never change what a template does.
+  pub(crate) fn apply(&self, template: &mut Template) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+
+    let template_name = template.name()?;
+
+    for (key, value) in &self.values {
+      let (name, path) = Segment::parse(key)?;
+
+      if Template::EFFECT_VARIABLES.contains(&name.as_str())
+        || !template.variables.contains_key(&name)
+      {
+        continue;
+      }
+
+      let invalid = |error: anyhow::Error| {
+        anyhow!(
+          "invalid value for `{key}` in template `{template_name}`: {error}"
+        )
+      };
+
+      let value = match template.schema(&name)? {
+        Some(_) if !path.is_empty() => {
+          bail!("cannot override `{key}`, `{name}` is not a mapping")
+        }
+        Some(schema) => match value {
+          Override::Raw(raw) => schema.coerce(raw),
+          Override::Typed(value) => schema.check(value),
+        }
+        .map_err(invalid)?,
+        None => {
+          let mut current = template.variables[&name].clone();
+
+          let value = match value {
+            Override::Raw(raw) => current
+              .get_path(&path)
+              .unwrap_or(&Value::Null)
+              .coerce(raw)
+              .map_err(invalid)?,
+            Override::Typed(value) => value.clone(),
+          };
+
+          if path.is_empty() {
+            value
+          } else {
+            current
+              .set_path(&path, value)
+              .map_err(|error| anyhow!("cannot override `{key}`: {error}"))?;
+
+            current
+          }
+        }
+      };
+
+      template.replace_variable(&name, value);
+
+      if !names.contains(&name) {
+        names.push(name);
+      }
+    }
+
+    Ok(names)
+  }
+
+  /// Adds the `SK_VAR_<name>` variables among `vars`, ignoring the others,
+  /// which need not be valid UTF-8.
+  pub(crate) fn env(
+    &mut self,
+    vars: impl IntoIterator<Item = (OsString, OsString)>,
+  ) -> Result {
+    for (key, value) in vars {
+      let Some(name) = key
+        .to_str()
+        .and_then(|key| key.strip_prefix(Self::ENV_PREFIX))
+      else {
+        continue;
+      };
+
+      let value = value.into_string().map_err(|_| {
+        anyhow!(
+          "environment variable `{}{name}` is not valid UTF-8",
+          Self::ENV_PREFIX
+        )
+      })?;
+
+      self.values.insert(name.to_owned(), Override::Raw(value));
+    }
+
+    Ok(())
+  }
+
+  /// Adds the variables in the YAML mapping at `path`.
+  pub(crate) fn file(&mut self, path: &Path) -> Result {
+    let content = fs::read_to_string(path)
+      .context(anyhow!("failed to read `{}`", path.display()))?;
+
+    self.values.extend(
+      serde_yaml::from_str::<Option<IndexMap<String, Value>>>(&content)
+        .context(anyhow!(
+          "`{}` must contain a mapping of variables",
+          path.display()
+        ))?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| (name, Override::Typed(value))),
+    );
+
+    Ok(())
+  }
+
+  /// Adds the variables in the JSON object `source`.
+  pub(crate) fn json(&mut self, source: &str) -> Result {
+    let values =
+      serde_json::from_str::<IndexMap<String, serde_json::Value>>(source)
+        .context("expected a JSON object of variables")?;
+
+    for (name, value) in values {
+      self
+        .values
+        .insert(name, Override::Typed(serde_yaml::to_value(value)?));
+    }
+
+    Ok(())
+  }
+
+  /// Adds a variable given as `name=value`.
+  pub(crate) fn var(&mut self, var: &str) -> Result {
+    let (name, value) = var.split_once('=').ok_or_else(|| {
+      anyhow!("invalid variable `{var}`, expected `name=value`")
+    })?;
+
+    Segment::parse(name.trim())?;
+
+    self
+      .values
+      .insert(name.trim().to_owned(), Override::Raw(value.to_owned()));
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn template(tempdir: &TempDir, frontmatter: &str) -> Template {
+    let file = tempdir.path().join("overrides.skeleton");
+
+    fs::write(&file, format!("---\n{frontmatter}---\ncontent\n")).unwrap();
+
+    Template::try_from(file).unwrap()
+  }
+
+  #[test]
+  fn precedence() {
+    let tempdir = TempDir::new("overrides").unwrap();
+
+    let vars_file = tempdir.path().join("answers.yaml");
+
+    fs::write(&vars_file, "name: file\nport: 80\nlicense: Apache-2.0\n")
+      .unwrap();
+
+    let mut overrides = Overrides::default();
+
+    overrides
+      .env([
+        ("SK_VAR_name".into(), "env".into()),
+        ("SK_VAR_debug".into(), "true".into()),
+        ("HOME".into(), "/root".into()),
+      ])
+      .unwrap();
+
+    overrides.file(&vars_file).unwrap();
+
+    overrides.json(r#"{"port": 8080, "tags": ["a"]}"#).unwrap();
+
+    overrides.var("name=cli").unwrap();
+
+    overrides.var("db.host=example.com").unwrap();
+
+    overrides.var("command=rm").unwrap();
+
+    overrides.var("undeclared=1").unwrap();
+
+    let mut template = template(
+      &tempdir,
+      indoc! {"
+        name: frontmatter
+        license: {type: choice, choices: [MIT, Apache-2.0], default: MIT}
+        db:
+          host: localhost
+          port: 5432
+        untouched: yes
+        debug: false
+        port: 80
+        tags: []
+        command: echo
+      "},
+    );
+
+    assert_eq!(
+      overrides.apply(&mut template).unwrap(),
+      ["name", "debug", "port", "license", "tags", "db"]
+    );
+
+    assert_eq!(
+      template.variables,
+      serde_yaml::from_str::<IndexMap<String, Value>>(indoc! {"
+        name: cli
+        license: Apache-2.0
+        db:
+          host: example.com
+          port: 5432
+        untouched: yes
+        debug: true
+        port: 8080
+        tags: [a]
+        command: echo
+      "})
+      .unwrap()
+    );
+  }
+
+  #[test]
+  fn types() {
+    let tempdir = TempDir::new("overrides").unwrap();
+
+    let mut overrides = Overrides::default();
+
+    overrides
+      .env([("SK_VAR_debug".into(), "no".into())])
+      .unwrap();
+
+    overrides.var("version=1.10").unwrap();
+    overrides.var("port=8080").unwrap();
+    overrides.var("db.port=6543").unwrap();
+    overrides.var("features=[cli, web]").unwrap();
+    overrides.var("level=3").unwrap();
+
+    let mut template = template(
+      &tempdir,
+      indoc! {"
+        version: \"1.0\"
+        port: 80
+        debug: true
+        db: {port: \"5432\"}
+        features: {type: multi-choice, choices: [cli, web]}
+        level: 1
+      "},
+    );
+
+    overrides.apply(&mut template).unwrap();
+
+    assert_eq!(
+      template.variables,
+      serde_yaml::from_str::<IndexMap<String, Value>>(indoc! {"
+        version: \"1.10\"
+        port: 8080
+        debug: false
+        db: {port: \"6543\"}
+        features: [cli, web]
+        level: 3
+      "})
+      .unwrap()
+    );
+
+    overrides.var("port=eighty").unwrap();
+
+    assert_eq!(
+      overrides.apply(&mut template).unwrap_err().to_string(),
+      "invalid value for `port` in template `overrides`: expected an integer"
+    );
+  }
+
+  #[test]
+  fn invalid() {
+    let tempdir = TempDir::new("overrides").unwrap();
+
+    let mut overrides = Overrides::default();
+
+    assert_eq!(
+      overrides.var("name").unwrap_err().to_string(),
+      "invalid variable `name`, expected `name=value`"
+    );
+
+    assert_eq!(
+      overrides.var("a b=c").unwrap_err().to_string(),
+      "invalid variable `a b`"
+    );
+
+    assert!(overrides.json("[1, 2]").is_err());
+
+    #[cfg(unix)]
+    {
+      use std::os::unix::ffi::OsStringExt;
+
+      let invalid = OsString::from_vec(vec![0xff]);
+
+      overrides
+        .env([
+          (invalid.clone(), "ignored".into()),
+          ("BAD".into(), invalid.clone()),
+        ])
+        .unwrap();
+
+      assert_eq!(
+        overrides
+          .env([("SK_VAR_bad".into(), invalid)])
+          .unwrap_err()
+          .to_string(),
+        "environment variable `SK_VAR_bad` is not valid UTF-8"
+      );
+    }
+
+    overrides.var("license=GPL").unwrap();
+
+    let mut template = template(
+      &tempdir,
+      "license: {type: choice, choices: [MIT, Apache-2.0]}\n",
+    );
+
+    assert_eq!(
+      overrides.apply(&mut template).unwrap_err().to_string(),
+      "invalid value for `license` in template `overrides`: expected one of \
+       `MIT`, `Apache-2.0`"
+    );
+  }
+}
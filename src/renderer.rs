@@ -0,0 +1,544 @@
+use super::*;
+
+pub(crate) struct Renderer<'a> {
+  blocks: HashMap<String, (&'a Template, &'a [Node])>,
+  builtins: Rc<Builtins>,
+  file: Option<&'a Path>,
+  stack: Vec<String>,
+  store: Option<&'a Store>,
+  template: &'a Template,
+  variable: Option<&'a str>,
+}
+
+impl<'a> Renderer<'a> {
+  /// Collects the blocks defined in `nodes`, including nested ones, which
+  /// override the blocks of the same name in a parent template.
+  fn collect_blocks<'b>(
+    template: &'b Template,
+    nodes: &'b [Node],
+    blocks: &mut HashMap<String, (&'b Template, &'b [Node])>,
+  ) {
+    for node in nodes {
+      if let Node::Block { body, name, .. } = node {
+        blocks.insert(name.clone(), (template, body.as_slice()));
+        Self::collect_blocks(template, body, blocks);
+      }
+    }
+  }
+
+  /// Reports `message` about the tag at `offset`, pointing at it in the
+  /// template's content, or naming the variable whose value is rendered.
+  fn diagnostic(&self, message: String, offset: usize) -> Result<Diagnostic> {
+    let Some(variable) = self.variable else {
+      let (base, body) = self.template.body()?;
+
+      let close = self.template.delimiters()?.close;
+
+      let length = body[offset..]
+        .find(&close)
+        .map_or(0, |end| end + close.len());
+
+      return Ok(Diagnostic::new(message, offset, length).locate(
+        &self.template.path,
+        &self.template.content,
+        base,
+      ));
+    };
+
+    Ok(Diagnostic::new(
+      format!(
+        "{message} in template `{}` in the value of `{variable}`",
+        self.template.name()?
+      ),
+      offset,
+      0,
+    ))
+  }
+
+  fn evaluate(
+    &self,
+    locals: &HashMap<String, Value>,
+    expression: &Expression,
+    offset: usize,
+  ) -> Result<Option<Value>> {
+    let root = locals
+      .get(&expression.name)
+      .or_else(|| self.template.variables.get(&expression.name))
+      .or_else(|| self.builtins.get(&expression.name, self.file));
+
+    let value = expression
+      .evaluate(root.and_then(|value| value.get_path(&expression.path)))?;
+
+    if value.is_none() && !self.template.allow_undefined() {
+      return Err(
+        self
+          .diagnostic(
+            format!("undefined variable `{}`", expression.variable()),
+            offset,
+          )?
+          .hint(
+            "pass `--allow-undefined` or set `allow_undefined: true` in the \
+             frontmatter to leave it as is",
+          )
+          .into(),
+      );
+    }
+
+    Ok(value)
+  }
+
+  /// Sets the path the template is applied to, available as `sk.file`.
+  pub(crate) fn file(self, file: &'a Path) -> Self {
+    Self {
+      file: Some(file),
+      ..self
+    }
+  }
+
+  /// Loads the template called `name` from the store, on behalf of an
+  /// `include` or `extends` tag at `offset`, with the variables in scope
+  /// overriding its own.
+  fn load(
+    &self,
+    name: &str,
+    kind: &str,
+    locals: &HashMap<String, Value>,
+    offset: usize,
+  ) -> Result<(Vec<String>, Template)> {
+    let mut stack = self.stack.clone();
+
+    stack.push(self.template.name()?);
+
+    if stack.iter().any(|ancestor| ancestor == name) {
+      bail!(
+        "{kind} cycle detected: {} -> `{name}`",
+        stack
+          .iter()
+          .map(|ancestor| format!("`{ancestor}`"))
+          .collect::<Vec<String>>()
+          .join(" -> ")
+      );
+    }
+
+    let store = self.store.ok_or_else(|| {
+      anyhow!("template `{name}` cannot be loaded without a store")
+    })?;
+
+    let mut template = store.template(name).map_err(|error| {
+      if error.is::<Diagnostic>() {
+        return error;
+      }
+
+      anyhow!(
+        "{error}, referenced from template `{}` {}",
+        stack.last().map(String::as_str).unwrap_or_default(),
+        self.location(offset)
+      )
+    })?;
+
+    let mut resolved = Vec::new();
+
+    for (variable, value) in self.template.variables.iter().chain(locals) {
+      if !Template::EFFECT_VARIABLES.contains(&variable.as_str()) {
+        template.replace_variable(variable, value.clone());
+        resolved.push(variable.clone());
+      }
+    }
+
+    if self.template.allow_undefined() {
+      template.replace_variable("allow_undefined", Value::Bool(true));
+    }
+
+    template.resolve(&resolved)?;
+
+    Ok((stack, template))
+  }
+
+  /// Describes where the tag at `offset` is, as a line of the template's
+  /// body, or as the variable whose value it's in, since `offset` is then a
+  /// position in that value rather than in the body.
+  fn location(&self, offset: usize) -> String {
+    match self.variable {
+      Some(variable) => format!("in the value of `{variable}`"),
+      None => format!("on line {}", self.template.line(offset)),
+    }
+  }
+
+  pub(crate) fn new(template: &'a Template) -> Self {
+    Self {
+      blocks: HashMap::new(),
+      builtins: Rc::default(),
+      file: None,
+      stack: Vec::new(),
+      store: None,
+      template,
+      variable: None,
+    }
+  }
+
+  pub(crate) fn render(&self) -> Result<String> {
+    let nodes = self.template.nodes()?;
+
+    let parent = nodes.iter().find_map(|node| match node {
+      Node::Extends { name, offset } => Some((name, *offset)),
+      _ => None,
+    });
+
+    match parent {
+      Some((name, offset)) => self.render_parent(name, &nodes, offset),
+      None => self.render_nodes(&nodes, &HashMap::new()),
+    }
+  }
+
+  /// Renders the `command` effect variable into a program and its arguments.
+  ///
+  /// The command is split into words like a shell would before its
+  /// placeholders are rendered, so that rendered values, such as a path with
+  /// spaces or quotes, always stay within their word. Tags are masked while
+  /// splitting, keeping the quotes and spaces within them, and the
+  /// backslashes escaping them, intact.
+  pub(crate) fn render_command(&self) -> Result<Vec<String>> {
+    const MARKER: char = '\u{e000}';
+
+    let Some(source) = self
+      .template
+      .variables
+      .get("command")
+      .and_then(Value::as_str)
+    else {
+      bail!(
+        "invalid template: {}, variable `command` must be a string",
+        self.template.path.display()
+      );
+    };
+
+    let Delimiters { close, open } = self.template.delimiters()?;
+
+    let mut tags = Vec::new();
+
+    let mut masked = String::new();
+
+    let mut rest = source;
+
+    while let Some(start) = rest.find(&open) {
+      let Some(end) = rest[start..].find(&close) else {
+        break;
+      };
+
+      let end = start + end + close.len();
+
+      // Backslashes before a tag escape it rather than the shell, so they
+      // are masked along with it.
+      let start = rest[..start].trim_end_matches('\\').len();
+
+      masked.push_str(&rest[..start]);
+      masked.push(MARKER);
+      masked.push_str(&tags.len().to_string());
+      masked.push(MARKER);
+
+      tags.push(&rest[start..end]);
+
+      rest = &rest[end..];
+    }
+
+    masked.push_str(rest);
+
+    shell_words::split(&masked)
+      .context(format!("failed to parse command `{source}`"))?
+      .iter()
+      .map(|word| {
+        let word = word
+          .split(MARKER)
+          .enumerate()
+          .map(|(index, part)| {
+            if index % 2 == 0 {
+              part
+            } else {
+              part
+                .parse::<usize>()
+                .ok()
+                .and_then(|tag| tags.get(tag).copied())
+                .unwrap_or(part)
+            }
+          })
+          .collect::<String>();
+
+        self.render_source("command", &word)
+      })
+      .collect()
+  }
+
+  fn render_include(
+    &self,
+    name: &str,
+    locals: &HashMap<String, Value>,
+    offset: usize,
+  ) -> Result<String> {
+    let (stack, template) = self.load(name, "include", locals, offset)?;
+
+    Renderer {
+      blocks: HashMap::new(),
+      builtins: self.builtins.clone(),
+      file: self.file,
+      stack,
+      store: self.store,
+      template: &template,
+      variable: None,
+    }
+    .render()
+  }
+
+  fn render_loop(
+    &self,
+    body: &[Node],
+    iterable: &Expression,
+    key: Option<&String>,
+    value: &str,
+    locals: &HashMap<String, Value>,
+    offset: usize,
+  ) -> Result<String> {
+    let (items, is_mapping) = match self.evaluate(locals, iterable, offset)? {
+      None | Some(Value::Null) => (Vec::new(), false),
+      Some(Value::Sequence(sequence)) => (
+        sequence
+          .into_iter()
+          .enumerate()
+          .map(|(index, item)| (Value::from(index), item))
+          .collect(),
+        false,
+      ),
+      Some(Value::Mapping(mapping)) => {
+        (mapping.into_iter().collect::<Vec<(Value, Value)>>(), true)
+      }
+      Some(_) => {
+        bail!("`{}` is not a sequence or mapping", iterable.variable())
+      }
+    };
+
+    let mut output = String::new();
+
+    for (item_key, item_value) in items {
+      let mut scope = locals.clone();
+
+      match key {
+        Some(key) => {
+          scope.insert(key.clone(), item_key);
+          scope.insert(value.to_owned(), item_value);
+        }
+        None if is_mapping => {
+          scope.insert(value.to_owned(), item_key);
+        }
+        None => {
+          scope.insert(value.to_owned(), item_value);
+        }
+      }
+
+      output.push_str(&self.render_nodes(body, &scope)?);
+    }
+
+    Ok(output)
+  }
+
+  fn render_nodes(
+    &self,
+    nodes: &[Node],
+    locals: &HashMap<String, Value>,
+  ) -> Result<String> {
+    let mut output = String::new();
+
+    for node in nodes {
+      match node {
+        Node::Conditional {
+          alternative,
+          condition,
+          consequent,
+          offset,
+        } => {
+          let branch = if self
+            .evaluate(locals, condition, *offset)?
+            .is_some_and(|value| value.is_truthy())
+          {
+            consequent
+          } else {
+            alternative
+          };
+
+          output.push_str(&self.render_nodes(branch, locals)?);
+        }
+        Node::Loop {
+          body,
+          iterable,
+          key,
+          offset,
+          value,
+        } => output.push_str(&self.render_loop(
+          body,
+          iterable,
+          key.as_ref(),
+          value,
+          locals,
+          *offset,
+        )?),
+        Node::Block { body, name, .. } => match self.blocks.get(name) {
+          Some((template, body)) => output.push_str(
+            &Renderer {
+              blocks: self.blocks.clone(),
+              builtins: self.builtins.clone(),
+              file: self.file,
+              stack: self.stack.clone(),
+              store: self.store,
+              template,
+              variable: self.variable,
+            }
+            .render_nodes(body, locals)?,
+          ),
+          None => output.push_str(&self.render_nodes(body, locals)?),
+        },
+        Node::Extends { offset, .. } => bail!(
+          "`extends` in template `{}` {} must not be nested",
+          self.template.name()?,
+          self.location(*offset)
+        ),
+        Node::Include { name, offset } => {
+          output.push_str(&self.render_include(name, locals, *offset)?);
+        }
+        Node::Text(text) => output.push_str(text),
+        Node::Variable {
+          expression,
+          offset,
+          source,
+        } => match self.evaluate(locals, expression, *offset)? {
+          Some(value) => {
+            output.push_str(&self.render_value(expression, value, *offset)?);
+          }
+          None => output.push_str(source),
+        },
+      }
+    }
+
+    Ok(output)
+  }
+
+  /// Renders the parent template called `name` in place of this one, with
+  /// the blocks defined in `nodes` overriding the parent's.
+  fn render_parent(
+    &self,
+    name: &str,
+    nodes: &[Node],
+    offset: usize,
+  ) -> Result<String> {
+    let (stack, parent) =
+      self.load(name, "inheritance", &HashMap::new(), offset)?;
+
+    let mut child = self.template.clone();
+
+    for (variable, value) in &parent.variables {
+      if !child.variables.contains_key(variable)
+        && !Template::EFFECT_VARIABLES.contains(&variable.as_str())
+      {
+        child.replace_variable(variable, value.clone());
+      }
+    }
+
+    let mut blocks = HashMap::new();
+
+    Self::collect_blocks(&child, nodes, &mut blocks);
+
+    blocks.extend(self.blocks.clone());
+
+    Renderer {
+      blocks,
+      builtins: self.builtins.clone(),
+      file: self.file,
+      stack,
+      store: self.store,
+      template: &parent,
+      variable: None,
+    }
+    .render()
+  }
+
+  /// Renders `source`, part of the value of the variable `name`, as a
+  /// template of its own.
+  fn render_source(&self, name: &'a str, source: &str) -> Result<String> {
+    let nodes = TemplateParser::parse(source, &self.template.delimiters()?)
+      .map_err(|error| {
+        anyhow!(
+          "invalid template: {}, variable `{name}`: {error}",
+          self.template.path.display()
+        )
+      })?;
+
+    Renderer {
+      blocks: HashMap::new(),
+      builtins: self.builtins.clone(),
+      file: self.file,
+      stack: self.stack.clone(),
+      store: self.store,
+      template: self.template,
+      variable: Some(name),
+    }
+    .render_nodes(&nodes, &HashMap::new())
+  }
+
+  /// Renders the value of a placeholder, writing scalars verbatim and
+  /// requiring mappings and sequences to go through a filter that picks
+  /// their format.
+  fn render_value(
+    &self,
+    expression: &Expression,
+    value: Value,
+    offset: usize,
+  ) -> Result<String> {
+    if value.is_null() {
+      return Ok(String::new());
+    }
+
+    if let Some(scalar) = value.scalar() {
+      return Ok(scalar);
+    }
+
+    Err(
+      self
+        .diagnostic(
+          format!(
+            "variable `{}` is a {}",
+            expression.variable(),
+            if value.is_sequence() {
+              "sequence"
+            } else {
+              "mapping"
+            }
+          ),
+          offset,
+        )?
+        .hint(
+          "render it with a filter such as `json`, `yaml`, `toml` or \
+           `join(\", \")`",
+        )
+        .into(),
+    )
+  }
+
+  /// Renders the value of the variable `name`, such as a computed variable or
+  /// the `filename` effect variable, as a template of its own.
+  pub(crate) fn render_variable(&self, name: &'a str) -> Result<String> {
+    let Some(source) =
+      self.template.variables.get(name).and_then(Value::as_str)
+    else {
+      bail!(
+        "invalid template: {}, variable `{name}` must be a string",
+        self.template.path.display()
+      );
+    };
+
+    self.render_source(name, source)
+  }
+
+  pub(crate) fn store(self, store: &'a Store) -> Self {
+    Self {
+      store: Some(store),
+      ..self
+    }
+  }
+}
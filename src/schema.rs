@@ -0,0 +1,396 @@
+use super::*;
+
+/// The type of a variable declared with a schema.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Kind {
+  Bool,
+  Choice,
+  Int,
+  MultiChoice,
+  Path,
+  String,
+}
+
+impl FromStr for Kind {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "bool" => Ok(Self::Bool),
+      "choice" => Ok(Self::Choice),
+      "int" => Ok(Self::Int),
+      "multi-choice" => Ok(Self::MultiChoice),
+      "path" => Ok(Self::Path),
+      "string" => Ok(Self::String),
+      _ => bail!("unknown variable type `{s}`"),
+    }
+  }
+}
+
+/// A free variable declared as a mapping with a `type`, such as
+/// `license: {type: choice, choices: [MIT, Apache-2.0], default: MIT}`.
+#[derive(Debug, Clone)]
+pub(crate) struct Schema {
+  ask: bool,
+  choices: Vec<String>,
+  default: Option<Value>,
+  kind: Kind,
+  prompt: Option<String>,
+  validate: Option<(String, Regex)>,
+}
+
+impl Schema {
+  const FIELDS: [&'static str; 5] =
+    ["choices", "default", "prompt", "type", "validate"];
+
+  /// Whether the variable is prompted for, which `prompt: false` turns off.
+  pub(crate) fn ask(&self) -> bool {
+    self.ask
+  }
+
+  /// Checks `value` against the schema, returning it converted to the
+  /// schema's type.
+  pub(crate) fn check(&self, value: &Value) -> Result<Value> {
+    let value = match self.kind {
+      Kind::Bool => match value {
+        Value::Bool(_) => value.clone(),
+        _ => Value::Bool(false).coerce(
+          &value
+            .scalar()
+            .ok_or_else(|| anyhow!("expected `true` or `false`"))?,
+        )?,
+      },
+      Kind::Choice => Value::String(self.choice(value)?),
+      Kind::Int => Value::from(0).coerce(
+        &value
+          .scalar()
+          .ok_or_else(|| anyhow!("expected an integer"))?,
+      )?,
+      Kind::MultiChoice => Value::Sequence(
+        value
+          .as_sequence()
+          .ok_or_else(|| anyhow!("expected a sequence of choices"))?
+          .iter()
+          .map(|item| self.choice(item).map(Value::String))
+          .collect::<Result<Vec<Value>>>()?,
+      ),
+      Kind::Path | Kind::String => Value::String(
+        value.scalar().ok_or_else(|| anyhow!("expected a string"))?,
+      ),
+    };
+
+    if let Some((pattern, regex)) = &self.validate {
+      let scalars = match &value {
+        Value::Sequence(sequence) => {
+          sequence.iter().filter_map(ValueExt::scalar).collect()
+        }
+        value => value.scalar().into_iter().collect::<Vec<String>>(),
+      };
+
+      for scalar in scalars {
+        if !regex.is_match(&scalar) {
+          bail!("`{scalar}` does not match `{pattern}`");
+        }
+      }
+    }
+
+    Ok(value)
+  }
+
+  fn choice(&self, value: &Value) -> Result<String> {
+    value
+      .scalar()
+      .filter(|scalar| self.choices.contains(scalar))
+      .ok_or_else(|| {
+        anyhow!(
+          "expected one of {}",
+          self
+            .choices
+            .iter()
+            .map(|choice| format!("`{choice}`"))
+            .collect::<Vec<String>>()
+            .join(", ")
+        )
+      })
+  }
+
+  /// Converts `answer`, given as a string on the command line or in the
+  /// environment, to a value of this schema's type.
+  pub(crate) fn coerce(&self, answer: &str) -> Result<Value> {
+    match self.kind {
+      Kind::MultiChoice => self.check(&Value::Null.coerce(answer)?),
+      _ => self.check(&Value::from(answer)),
+    }
+  }
+
+  pub(crate) fn default(&self) -> Option<&Value> {
+    self.default.as_ref()
+  }
+
+  /// Parses `value` as a schema, returning `None` if it isn't a mapping with
+  /// a known `type` and no other keys than schema fields, such as
+  /// `db: {type: postgres}`, which is left as a plain mapping.
+  pub(crate) fn parse(value: &Value) -> Result<Option<Self>> {
+    let Value::Mapping(mapping) = value else {
+      return Ok(None);
+    };
+
+    if !mapping.contains_key("type")
+      || !mapping
+        .keys()
+        .all(|key| key.as_str().is_some_and(|key| Self::FIELDS.contains(&key)))
+    {
+      return Ok(None);
+    }
+
+    let Some(kind) = mapping["type"]
+      .as_str()
+      .and_then(|kind| kind.parse::<Kind>().ok())
+    else {
+      return Ok(None);
+    };
+
+    let choices = match mapping.get("choices") {
+      None => Vec::new(),
+      Some(choices) => choices
+        .as_sequence()
+        .and_then(|choices| {
+          choices.iter().map(ValueExt::scalar).collect::<Option<_>>()
+        })
+        .ok_or_else(|| anyhow!("`choices` must be a sequence of scalars"))?,
+    };
+
+    match kind {
+      Kind::Choice | Kind::MultiChoice if choices.is_empty() => {
+        bail!("`choices` must not be empty")
+      }
+      Kind::Bool | Kind::Int | Kind::Path | Kind::String
+        if !choices.is_empty() =>
+      {
+        bail!("`choices` only applies to `choice` and `multi-choice` variables")
+      }
+      _ => {}
+    }
+
+    let (ask, prompt) = match mapping.get("prompt") {
+      None => (true, None),
+      Some(Value::Bool(ask)) => (*ask, None),
+      Some(Value::String(prompt)) => (true, Some(prompt.clone())),
+      Some(_) => bail!("`prompt` must be a string or a boolean"),
+    };
+
+    let validate = match mapping.get("validate") {
+      None => None,
+      Some(Value::String(pattern)) => Some((
+        pattern.clone(),
+        Regex::new(&format!("^(?:{pattern})$"))
+          .map_err(|error| anyhow!("invalid `validate` pattern: {error}"))?,
+      )),
+      Some(_) => bail!("`validate` must be a string"),
+    };
+
+    let mut schema = Self {
+      ask,
+      choices,
+      default: None,
+      kind,
+      prompt,
+      validate,
+    };
+
+    if let Some(default) = mapping.get("default").filter(|d| !d.is_null()) {
+      schema.default = Some(
+        schema
+          .check(default)
+          .map_err(|error| anyhow!("invalid `default`: {error}"))?,
+      );
+    }
+
+    Ok(Some(schema))
+  }
+
+  /// Asks for the value of the variable `name` with the widget matching the
+  /// schema's type.
+  pub(crate) fn prompt(&self, name: &str, theme: &dyn Theme) -> Result<Value> {
+    let prompt = self
+      .prompt
+      .clone()
+      .unwrap_or_else(|| format!("Enter value for `{}`", name.bold()));
+
+    Ok(match self.kind {
+      Kind::Bool => Value::Bool(
+        Confirm::with_theme(theme)
+          .with_prompt(prompt)
+          .default(self.default.as_ref().is_some_and(ValueExt::is_truthy))
+          .interact()?,
+      ),
+      Kind::Choice => {
+        let default = self
+          .default
+          .as_ref()
+          .and_then(ValueExt::scalar)
+          .and_then(|default| {
+            self.choices.iter().position(|choice| *choice == default)
+          })
+          .unwrap_or_default();
+
+        Value::String(
+          self.choices[Select::with_theme(theme)
+            .with_prompt(prompt)
+            .items(&self.choices)
+            .default(default)
+            .interact()?]
+          .clone(),
+        )
+      }
+      Kind::MultiChoice => {
+        let defaults = self
+          .default
+          .as_ref()
+          .and_then(Value::as_sequence)
+          .map(|default| {
+            self
+              .choices
+              .iter()
+              .map(|choice| {
+                default
+                  .iter()
+                  .any(|item| item.scalar().as_ref() == Some(choice))
+              })
+              .collect::<Vec<bool>>()
+          })
+          .unwrap_or_default();
+
+        Value::Sequence(
+          MultiSelect::with_theme(theme)
+            .with_prompt(prompt)
+            .items(&self.choices)
+            .defaults(&defaults)
+            .interact()?
+            .into_iter()
+            .map(|index| Value::String(self.choices[index].clone()))
+            .collect(),
+        )
+      }
+      Kind::Int | Kind::Path | Kind::String => {
+        let mut input = Input::<String>::with_theme(theme)
+          .with_prompt(prompt)
+          .validate_with(|input: &String| {
+            self
+              .check(&Value::String(input.clone()))
+              .map(|_| ())
+              .map_err(|error| error.to_string())
+          });
+
+        if let Some(default) = self.default.as_ref().and_then(ValueExt::scalar)
+        {
+          input = input.default(default);
+        }
+
+        self.check(&Value::String(input.interact_text()?))?
+      }
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn schema(source: &str) -> Result<Option<Schema>> {
+    Schema::parse(&serde_yaml::from_str(source).unwrap())
+  }
+
+  #[test]
+  fn parse() {
+    let schema = schema(
+      "{type: choice, choices: [MIT, Apache-2.0], default: MIT, prompt: License?}",
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(schema.kind, Kind::Choice);
+    assert_eq!(schema.choices, ["MIT", "Apache-2.0"]);
+    assert_eq!(schema.default(), Some(&Value::from("MIT")));
+    assert_eq!(schema.prompt.as_deref(), Some("License?"));
+    assert!(schema.ask());
+
+    let schema = self::schema("{type: bool, default: true, prompt: false}")
+      .unwrap()
+      .unwrap();
+
+    assert!(!schema.ask());
+    assert_eq!(schema.default(), Some(&Value::Bool(true)));
+  }
+
+  #[test]
+  fn plain_values() {
+    assert!(schema("foo").unwrap().is_none());
+    assert!(schema("{host: localhost}").unwrap().is_none());
+    assert!(schema("{type: postgres, host: localhost}")
+      .unwrap()
+      .is_none());
+    assert!(schema("{type: postgres}").unwrap().is_none());
+    assert!(schema("{type: 1}").unwrap().is_none());
+  }
+
+  #[test]
+  fn invalid() {
+    for (source, message) in [
+      ("{type: choice}", "`choices` must not be empty"),
+      (
+        "{type: int, choices: [1]}",
+        "`choices` only applies to `choice` and `multi-choice` variables",
+      ),
+      (
+        "{type: int, default: one}",
+        "invalid `default`: expected an integer",
+      ),
+      (
+        "{type: choice, choices: [a], default: b}",
+        "invalid `default`: expected one of `a`",
+      ),
+      (
+        "{type: string, validate: '[a-z]+', default: A}",
+        "invalid `default`: `A` does not match `[a-z]+`",
+      ),
+    ] {
+      assert_eq!(schema(source).unwrap_err().to_string(), message);
+    }
+  }
+
+  #[test]
+  fn check() {
+    let schema = schema("{type: int, validate: '[0-9]{2}'}")
+      .unwrap()
+      .unwrap();
+
+    assert_eq!(schema.check(&Value::from("42")).unwrap(), Value::from(42));
+
+    assert_eq!(
+      schema.check(&Value::from("7")).unwrap_err().to_string(),
+      "`7` does not match `[0-9]{2}`"
+    );
+
+    let schema = self::schema(
+      "{type: multi-choice, choices: [cli, core, web], default: [cli]}",
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+      schema
+        .check(&serde_yaml::from_str("[web, core]").unwrap())
+        .unwrap(),
+      serde_yaml::from_str::<Value>("[web, core]").unwrap()
+    );
+
+    assert_eq!(
+      schema
+        .check(&serde_yaml::from_str("[api]").unwrap())
+        .unwrap_err()
+        .to_string(),
+      "expected one of `cli`, `core`, `web`"
+    );
+  }
+}
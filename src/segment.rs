@@ -0,0 +1,116 @@
+use super::*;
+
+/// A single step into a nested variable, as in `authors[0]` or `db.host`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Segment {
+  Index(usize),
+  Key(String),
+}
+
+impl Display for Segment {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Segment::Index(index) => write!(f, "[{index}]"),
+      Segment::Key(key) => write!(f, ".{key}"),
+    }
+  }
+}
+
+impl Segment {
+  /// Formats a variable name followed by `path`, e.g. `authors[0].email`.
+  pub(crate) fn display(name: &str, path: &[Segment]) -> String {
+    format!(
+      "{name}{}",
+      path.iter().map(ToString::to_string).collect::<String>()
+    )
+  }
+
+  /// Parses a variable reference into its root name and the segments that
+  /// follow it.
+  pub(crate) fn parse(source: &str) -> Result<(String, Vec<Segment>)> {
+    let invalid = || anyhow!("invalid variable `{source}`");
+
+    let is_key = |key: &str| {
+      !key.is_empty()
+        && !key
+          .chars()
+          .any(|c| c.is_whitespace() || matches!(c, '.' | '[' | ']'))
+    };
+
+    let end = source.find(['.', '[']).unwrap_or(source.len());
+
+    let name = &source[..end];
+
+    if !is_key(name) {
+      return Err(invalid());
+    }
+
+    let mut path = Vec::new();
+
+    let mut rest = &source[end..];
+
+    while !rest.is_empty() {
+      if let Some(after) = rest.strip_prefix('.') {
+        let end = after.find(['.', '[']).unwrap_or(after.len());
+
+        if !is_key(&after[..end]) {
+          return Err(invalid());
+        }
+
+        path.push(Segment::Key(after[..end].to_owned()));
+
+        rest = &after[end..];
+      } else {
+        let (index, after) = rest
+          .strip_prefix('[')
+          .and_then(|after| after.split_once(']'))
+          .ok_or_else(invalid)?;
+
+        path.push(Segment::Index(index.trim().parse().map_err(|_| invalid())?));
+
+        rest = after;
+      }
+    }
+
+    Ok((name.to_owned(), path))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse() {
+    assert_eq!(
+      Segment::parse("name").unwrap(),
+      ("name".to_owned(), Vec::new())
+    );
+
+    assert_eq!(
+      Segment::parse("authors[0].email").unwrap(),
+      (
+        "authors".to_owned(),
+        vec![Segment::Index(0), Segment::Key("email".to_owned())]
+      )
+    );
+
+    assert_eq!(
+      Segment::display(
+        "authors",
+        &Segment::parse("authors[0].email").unwrap().1
+      ),
+      "authors[0].email"
+    );
+  }
+
+  #[test]
+  fn invalid() {
+    for source in ["", "a b", "a.", "a[x]", "a[0", ".a", "a..b"] {
+      assert_eq!(
+        Segment::parse(source).unwrap_err().to_string(),
+        format!("invalid variable `{source}`")
+      );
+    }
+  }
+}
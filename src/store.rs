@@ -29,6 +29,43 @@ impl Store {
     )
   }
 
+  /// Whether `path` is a directory template, a directory with a manifest,
+  /// whose files belong to it rather than being templates of their own.
+  fn is_directory_template(path: &Path) -> bool {
+    path.join(Template::MANIFEST).is_file()
+  }
+
+  /// Whether the file at `path` is the sidecar of a plain template next to
+  /// it, rather than a template of its own.
+  fn is_sidecar(path: &Path) -> bool {
+    path
+      .to_str()
+      .and_then(|path| path.strip_suffix(Template::SIDECAR_EXTENSION))
+      .is_some_and(|template| {
+        !template.ends_with(TEMPLATE_EXTENSION) && Path::new(template).is_file()
+      })
+  }
+
+  /// Whether the store's walk visits `entry`, skipping hidden directories,
+  /// such as `.git`, and the contents of directory templates.
+  fn is_walked(entry: &DirEntry) -> bool {
+    if entry.depth() == 0 {
+      return true;
+    }
+
+    if entry.file_type().is_dir()
+      && entry.file_name().to_string_lossy().starts_with('.')
+    {
+      return false;
+    }
+
+    entry.depth() == 1
+      || !entry
+        .path()
+        .parent()
+        .is_some_and(Self::is_directory_template)
+  }
+
   pub(crate) fn load() -> Result<Self> {
     Ok(Self {
       path: dirs::home_dir()
@@ -38,6 +75,43 @@ impl Store {
     })
   }
 
+  /// Returns the paths of the templates in the store, without reading them.
+  fn paths(&self) -> Vec<PathBuf> {
+    WalkDir::new(&self.path)
+      .into_iter()
+      .filter_entry(Self::is_walked)
+      .filter_map(Result::ok)
+      .filter(|e| {
+        e.file_type().is_file() && !Self::is_sidecar(e.path())
+          || e.depth() > 0 && Self::is_directory_template(e.path())
+      })
+      .map(DirEntry::into_path)
+      .collect()
+  }
+
+  /// Reads the template called `name`, looking it up by path so that the
+  /// rest of the store isn't read, as `include` and `extends` tags do this
+  /// for every template they render.
+  pub(crate) fn template(&self, name: &str) -> Result<Template> {
+    let path = self
+      .paths()
+      .into_iter()
+      .find(|path| {
+        path
+          .file_name()
+          .and_then(OsStr::to_str)
+          .is_some_and(|file_name| {
+            file_name
+              .strip_suffix(TEMPLATE_EXTENSION)
+              .unwrap_or(file_name)
+              == name
+          })
+      })
+      .ok_or_else(|| anyhow!("template `{name}` does not exist"))?;
+
+    Template::try_from(path)
+  }
+
   /// Retrieves all templates, optionally filtered by group names.
   ///
   /// This method returns a list of all templates if no groups are specified,
@@ -70,28 +144,34 @@ impl Store {
     &self,
     groups: Option<Vec<String>>,
   ) -> Result<Vec<Template>> {
-    let all_templates = WalkDir::new(&self.path)
+    self.try_templates(groups).into_iter().collect()
+  }
+
+  /// Reads every template like `templates`, but keeps going past those that
+  /// fail to read, returning their errors in their place. Errors are kept
+  /// whatever the `groups`, since a template that can't be read can't be
+  /// checked against them.
+  pub(crate) fn try_templates(
+    &self,
+    groups: Option<Vec<String>>,
+  ) -> Vec<Result<Template>> {
+    let groups = groups.unwrap_or_default();
+
+    self
+      .paths()
       .into_iter()
-      .filter_map(Result::ok)
-      .filter(|e| e.file_type().is_file())
-      .map(|e| Template::try_from(e.into_path()))
-      .collect::<Result<Vec<Template>>>()?;
-
-    match groups {
-      Some(groups) if !groups.is_empty() => Ok(
-        all_templates
-          .into_iter()
-          .filter(|template| {
-            template.groups().is_some_and(|template_groups| {
-              template_groups.iter().any(|group| {
-                groups.contains(&group.as_str().unwrap_or_default().to_owned())
-              })
+      .map(Template::try_from)
+      .filter(|template| match template {
+        Ok(template) if !groups.is_empty() => {
+          template.groups().is_some_and(|template_groups| {
+            template_groups.iter().any(|group| {
+              groups.contains(&group.as_str().unwrap_or_default().to_owned())
             })
           })
-          .collect(),
-      ),
-      _ => Ok(all_templates),
-    }
+        }
+        _ => true,
+      })
+      .collect()
   }
 
   pub(crate) fn write(&self, name: &str, content: &str) -> Result {
@@ -163,6 +243,50 @@ mod tests {
     assert!(!store.exists(template_name).unwrap());
   }
 
+  #[test]
+  fn template_by_name() {
+    let temp_dir = TempDir::new("test").unwrap();
+
+    let store = Store::try_from(temp_dir.into_path()).unwrap();
+
+    store.write("test_template", DEFAULT_TEMPLATE).unwrap();
+
+    store.write("invalid", "---\nfilename: [\n---\n").unwrap();
+
+    assert_eq!(
+      store.template("test_template").unwrap().name().unwrap(),
+      "test_template"
+    );
+
+    assert!(store.template("invalid").is_err());
+
+    assert_eq!(
+      store.template("missing").unwrap_err().to_string(),
+      "template `missing` does not exist"
+    );
+  }
+
+  #[test]
+  fn invalid_templates() {
+    let temp_dir = TempDir::new("test").unwrap();
+
+    let store = Store::try_from(temp_dir.into_path()).unwrap();
+
+    store.write("valid", DEFAULT_TEMPLATE).unwrap();
+
+    store.write("invalid", "---\nfilename: [\n---\n").unwrap();
+
+    assert!(store.templates(None).is_err());
+
+    let templates = store.try_templates(Some(vec!["other".into()]));
+
+    assert_eq!(templates.len(), 1);
+
+    assert!(templates[0].is_err());
+
+    assert_eq!(store.try_templates(None).len(), 2);
+  }
+
   #[test]
   fn read_template_content() {
     let temp_dir = TempDir::new("test").unwrap();
@@ -303,4 +427,58 @@ mod tests {
 
     assert_eq!(all_templates.len(), 3);
   }
+
+  #[test]
+  fn plain_files_and_sidecars() {
+    let temp_dir = TempDir::new("test").unwrap();
+
+    let store = Store::try_from(temp_dir.into_path()).unwrap();
+
+    store.write("test_template", DEFAULT_TEMPLATE).unwrap();
+
+    fs::write(store.path.join(".editorconfig"), "root = true\n").unwrap();
+    fs::write(store.path.join("ci.yaml"), "on: push\n").unwrap();
+    fs::write(store.path.join("ci.yaml.yaml"), "filename: ci.yml\n").unwrap();
+
+    fs::create_dir(store.path.join(".git")).unwrap();
+    fs::write(store.path.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+
+    let mut names = store
+      .templates(None)
+      .unwrap()
+      .iter()
+      .map(|template| template.name().unwrap())
+      .collect::<Vec<String>>();
+
+    names.sort();
+
+    assert_eq!(names, [".editorconfig", "ci.yaml", "test_template"]);
+
+    assert_eq!(
+      store.template("ci.yaml").unwrap().filename(),
+      Some(Value::from("ci.yml"))
+    );
+  }
+
+  #[test]
+  fn directory_templates() {
+    let temp_dir = TempDir::new("test").unwrap();
+
+    let store = Store::try_from(temp_dir.into_path()).unwrap();
+
+    let directory = store.path.join("rust-cli");
+
+    fs::create_dir_all(directory.join("src")).unwrap();
+
+    fs::write(directory.join(Template::MANIFEST), "name: demo\n").unwrap();
+    fs::write(directory.join("Cargo.toml"), "[package]\n").unwrap();
+    fs::write(directory.join("src/main.rs"), "fn main() {}\n").unwrap();
+
+    let templates = store.templates(None).unwrap();
+
+    assert_eq!(templates.len(), 1);
+    assert_eq!(templates[0].name().unwrap(), "rust-cli");
+    assert_eq!(templates[0].kind, TemplateKind::Directory);
+    assert_eq!(templates[0].entries().unwrap().len(), 2);
+  }
 }
@@ -2,110 +2,220 @@ use super::*;
 
 #[derive(Debug, Parser)]
 pub(crate) struct Apply {
+  #[clap(
+    long,
+    help = "Leave placeholders referring to undefined variables as they are"
+  )]
+  allow_undefined: bool,
   #[clap(short, long, help = "Groups to filter templates by")]
   groups: Option<Vec<String>>,
   #[clap(short, long, help = "Interactive mode")]
   interactive: bool,
   #[clap(short, long, help = "Overwrite existing files")]
   overwrite: bool,
+  #[clap(
+    long,
+    value_name = "NAME=VALUE",
+    help = "Set a variable, overriding the frontmatter"
+  )]
+  var: Vec<String>,
+  #[clap(
+    long,
+    value_name = "PATH",
+    help = "Set variables from a YAML file, overriding the frontmatter"
+  )]
+  vars_file: Option<PathBuf>,
+  #[clap(
+    long,
+    value_name = "PATH",
+    help = "Set variables from a JSON file, or `-` for standard input"
+  )]
+  vars_json: Option<PathBuf>,
 }
 
 impl Apply {
+  /// Prompts for the value of the free variable `variable` of `template`.
+  fn ask(
+    template: &Template,
+    variable: &str,
+    theme: &dyn Theme,
+  ) -> Result<Value> {
+    if let Some(schema) = template.schema(variable)? {
+      return schema.prompt(variable, theme);
+    }
+
+    let mut value = template.variables[variable].clone();
+
+    for path in value.leaves() {
+      let current = value.get_path(&path).cloned().unwrap_or_default();
+
+      let mut input = Input::<String>::with_theme(theme)
+        .with_prompt(format!(
+          "Enter value for `{}`",
+          Segment::display(variable, &path).bold()
+        ))
+        .validate_with(|answer: &String| {
+          current
+            .coerce(answer)
+            .map(|_| ())
+            .map_err(|error| error.to_string())
+        });
+
+      if let Some(default) = current.scalar() {
+        input = input.default(default);
+      }
+
+      let answer = current.coerce(&input.interact_text()?)?;
+
+      value.set_path(&path, answer)?;
+    }
+
+    Ok(value)
+  }
+
+  /// Collects the variable overrides from the environment, `--vars-file`,
+  /// `--vars-json` and `--var`, in increasing order of precedence.
+  fn overrides(&self) -> Result<Overrides> {
+    let mut overrides = Overrides::default();
+
+    overrides.env(env::vars_os())?;
+
+    if let Some(path) = &self.vars_file {
+      overrides.file(path)?;
+    }
+
+    if let Some(path) = &self.vars_json {
+      let source = if path.as_os_str() == "-" {
+        io::read_to_string(io::stdin())
+          .context("failed to read variables from standard input")?
+      } else {
+        fs::read_to_string(path)
+          .context(anyhow!("failed to read `{}`", path.display()))?
+      };
+
+      overrides
+        .json(&source)
+        .context(anyhow!("invalid variables in `{}`", path.display()))?;
+    }
+
+    for var in &self.var {
+      overrides.var(var)?;
+    }
+
+    Ok(overrides)
+  }
+
   pub(crate) fn run(self, store: &Store) -> Result<()> {
-    let mut templates = Search::<Template>::with(store.templates(self.groups)?)
-      .run()
-      .context("failed to locate template")?;
+    let overrides = self.overrides()?;
 
-    let effect_variables = ["filename", "command", "groups"];
+    let mut templates =
+      Search::<Template>::with(store.templates(self.groups.clone())?)
+        .run()
+        .context("failed to locate template")?;
+
+    let theme = ColorfulTheme::default();
+
+    // Answers keyed by variable name and declared value, so that a variable
+    // shared by several templates is only asked for once, unless they
+    // declare it differently.
+    let mut answers = HashMap::<(String, Value), Value>::new();
 
     for template in &mut templates {
       let name = template.name()?;
 
-      let filename = template.filename().ok_or_else(|| {
-        anyhow!("template `{}` does not specify a filename", name.bold())
-      })?;
+      if template.filename().is_none() {
+        bail!("template `{}` does not specify a filename", name.bold());
+      }
+
+      let overridden = overrides.apply(template)?;
+
+      let computed_variables = template.computed_variables()?;
 
       let free_variables = template
         .variables
         .keys()
-        .filter(|k| !effect_variables.contains(&k.as_str()))
+        .filter(|k| {
+          !Template::EFFECT_VARIABLES.contains(&k.as_str())
+            && !computed_variables.contains(k)
+            && !overridden.contains(k)
+        })
         .cloned()
         .collect::<Vec<_>>();
 
       if self.interactive {
-        let theme = ColorfulTheme::default();
-
         for variable in free_variables {
-          template.replace_variable(
-            &variable,
-            serde_yaml::to_value(
-              Input::<String>::with_theme(&theme)
-                .with_prompt(format!("Enter value for `{}`", variable.bold()))
-                .interact_text()?,
-            )?,
-          );
-        }
-      }
-
-      let file_path =
-        std::env::current_dir()?.join(filename.as_str().unwrap_or_default());
-
-      if file_path.exists() && !self.overwrite {
-        if self.interactive {
-          let theme = ColorfulTheme::default();
-
-          let overwrite_confirmed = Confirm::with_theme(&theme)
-            .with_prompt(format!(
-              "File `{}` already exists. Overwrite?",
-              file_path.display()
-            ))
-            .interact()?;
-
-          if !overwrite_confirmed {
-            println!("Skipping file `{}`", file_path.display());
+          if template
+            .schema(&variable)?
+            .is_some_and(|schema| !schema.ask())
+          {
             continue;
           }
-        } else {
-          println!(
-            "File `{}` already exists, specify `--overwrite` to overwrite it",
-            file_path.display()
-          );
 
-          continue;
+          let key = (variable.clone(), template.variables[&variable].clone());
+
+          let value = if let Some(answer) = answers.get(&key) {
+            answer.clone()
+          } else {
+            let answer = Self::ask(template, &variable, &theme)?;
+            answers.insert(key, answer.clone());
+            answer
+          };
+
+          template.replace_variable(&variable, value);
         }
       }
 
-      if let Some(parent) = file_path.parent() {
-        fs::create_dir_all(parent).context(anyhow!(
-          "failed to create directories for `{}`",
-          file_path.display()
-        ))?;
+      if self.allow_undefined {
+        template.replace_variable("allow_undefined", Value::Bool(true));
       }
 
-      let mut content = template.substitute()?;
+      template.resolve(&[])?;
+
+      let filename = Renderer::new(template)
+        .store(store)
+        .render_variable("filename")?;
 
-      if !content.ends_with('\n') {
-        content.push('\n');
+      if filename.trim().is_empty() {
+        bail!("template `{}` has an empty filename", name.bold());
       }
 
-      fs::write(&file_path, content)
-        .context(anyhow!("failed to write file `{}`", file_path.display()))?;
+      let file_path = std::env::current_dir()?.join(filename);
+
+      if template.kind == TemplateKind::Directory {
+        for entry in template.entries()? {
+          let path = Renderer::new(&entry)
+            .store(store)
+            .render_variable("filename")?;
+
+          if Template::is_skipped(&path) {
+            continue;
+          }
+
+          self.write(store, &entry, &file_path.join(path), &theme)?;
+        }
+      } else if !self.write(store, template, &file_path, &theme)? {
+        continue;
+      }
 
       println!("Applied template `{name}` to `{}`", file_path.display());
 
-      if let Some(command) = template.command() {
-        let mut command_parts =
-          command.as_str().unwrap_or_default().split_whitespace();
+      if template.command().is_some_and(|command| !command.is_null()) {
+        let renderer = Renderer::new(template).store(store).file(&file_path);
+
+        let mut command_parts = renderer.render_command()?.into_iter();
 
         let command_name = command_parts
           .next()
           .ok_or(anyhow!("command for template `{}` is empty", name.bold()))?;
 
-        let command_args: Vec<_> = command_parts.collect();
+        let mut command_args: Vec<_> = command_parts.collect();
+
+        if !template.refers_to("command", "sk.file")? {
+          command_args.push(file_path.display().to_string());
+        }
 
-        let output = Command::new(command_name)
+        let output = Command::new(&command_name)
           .args(command_args)
-          .arg(&file_path)
           .output()
           .context(format!("failed to execute command: {command_name}"))?;
 
@@ -121,4 +231,67 @@ impl Apply {
 
     Ok(())
   }
+
+  /// Writes `template` to `file_path`, asking before overwriting an existing
+  /// file when interactive, and returns whether it was written.
+  fn write(
+    &self,
+    store: &Store,
+    template: &Template,
+    file_path: &Path,
+    theme: &dyn Theme,
+  ) -> Result<bool> {
+    if file_path.exists() && !self.overwrite {
+      if self.interactive {
+        let overwrite_confirmed = Confirm::with_theme(theme)
+          .with_prompt(format!(
+            "File `{}` already exists. Overwrite?",
+            file_path.display()
+          ))
+          .interact()?;
+
+        if !overwrite_confirmed {
+          println!("Skipping file `{}`", file_path.display());
+          return Ok(false);
+        }
+      } else {
+        println!(
+          "File `{}` already exists, specify `--overwrite` to overwrite it",
+          file_path.display()
+        );
+
+        return Ok(false);
+      }
+    }
+
+    if let Some(parent) = file_path.parent() {
+      fs::create_dir_all(parent).context(anyhow!(
+        "failed to create directories for `{}`",
+        file_path.display()
+      ))?;
+    }
+
+    let content = match &template.binary {
+      Some(bytes) => bytes.clone(),
+      None => Renderer::new(template)
+        .store(store)
+        .file(file_path)
+        .render()?
+        .into_bytes(),
+    };
+
+    fs::write(file_path, content)
+      .context(anyhow!("failed to write file `{}`", file_path.display()))?;
+
+    #[cfg(unix)]
+    if let Some(mode) = template.mode()? {
+      fs::set_permissions(file_path, fs::Permissions::from_mode(mode))
+        .context(anyhow!(
+          "failed to set the mode of `{}`",
+          file_path.display()
+        ))?;
+    }
+
+    Ok(true)
+  }
 }
@@ -20,24 +20,58 @@ impl Edit {
     for template in templates {
       let name = template.name()?;
 
+      if template.kind == TemplateKind::Directory {
+        bail!(
+          "template `{}` is a directory, edit the files in `{}` instead",
+          name.bold(),
+          template.path.display()
+        );
+      }
+
+      if template.binary.is_some() {
+        bail!("template `{}` is binary and cannot be edited", name.bold());
+      }
+
       let tempdir = TempDir::new(&format!("edit-{name}"))?;
 
-      let file = tempdir
-        .path()
-        .join(format!("{}{TEMPLATE_EXTENSION}", template.name()?));
+      let file = tempdir.path().join(
+        template
+          .path
+          .file_name()
+          .context("failed to get template file name")?,
+      );
 
       fs::write(&file, &template.content)?;
 
-      let status = Command::new(&editor)
-        .arg(&file)
-        .status()
-        .context("failed to open temporary file in editor")?;
+      loop {
+        let status = Command::new(&editor)
+          .arg(&file)
+          .status()
+          .context("failed to open temporary file in editor")?;
+
+        if !status.success() {
+          bail!("editor exited with non-zero status");
+        }
+
+        let Err(error) =
+          Template::try_from(file.clone()).and_then(|edited| edited.nodes())
+        else {
+          break;
+        };
+
+        eprintln!("{}: {error}", "error".red().bold());
 
-      if !status.success() {
-        bail!("editor exited with non-zero status");
+        if !Confirm::with_theme(&ColorfulTheme::default())
+          .with_prompt(format!("Edit `{}` again?", name.bold()))
+          .default(true)
+          .interact()?
+        {
+          bail!("discarded invalid changes to `{}`", name.bold());
+        }
       }
 
-      store.write(&name, &fs::read_to_string(&file)?)?;
+      fs::write(&template.path, fs::read_to_string(&file)?)
+        .context("failed to write template")?;
 
       println!("Saved changes to `{}` successfully.", name.bold());
     }
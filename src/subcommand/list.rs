@@ -8,7 +8,16 @@ pub(crate) struct List {
 
 impl List {
   pub(crate) fn run(self, store: &Store) -> Result<()> {
-    let mut templates = store.templates(self.groups)?;
+    let mut errors = Vec::new();
+
+    let mut templates = Vec::new();
+
+    for template in store.try_templates(self.groups) {
+      match template {
+        Ok(template) => templates.push(template),
+        Err(error) => errors.push(error),
+      }
+    }
 
     templates.sort_by(|a, b| {
       a.name()
@@ -17,9 +26,24 @@ impl List {
     });
 
     for template in templates {
+      if template.kind != TemplateKind::Directory && template.binary.is_none() {
+        if let Err(error) = template.nodes() {
+          errors.push(error);
+          continue;
+        }
+      }
+
       println!("{}", template.name()?);
     }
 
+    for error in &errors {
+      eprintln!("{}: {error}", "error".red().bold());
+    }
+
+    if !errors.is_empty() {
+      bail!("{} invalid template(s)", errors.len());
+    }
+
     Ok(())
   }
 }
@@ -8,7 +8,18 @@ pub(crate) fn run(store: &Store) -> Result {
     .context("Failed to locate template")?;
 
   for template in &templates {
+    if template.kind == TemplateKind::Directory {
+      fs::remove_dir_all(&template.path)?;
+      continue;
+    }
+
     fs::remove_file(&template.path)?;
+
+    let sidecar = Template::sidecar(&template.path);
+
+    if template.kind == TemplateKind::Plain && sidecar.is_file() {
+      fs::remove_file(sidecar)?;
+    }
   }
 
   let names = templates
@@ -32,9 +32,22 @@ pub(crate) fn run(store: &Store) -> Result {
     }
 
     if new_name != old_name && !store.exists(&new_name)? {
-      let content = fs::read_to_string(&template.path)?;
-      store.write(&new_name, &content)?;
-      fs::remove_file(&template.path)?;
+      if template.kind == TemplateKind::Skeleton {
+        let content = fs::read_to_string(&template.path)?;
+        store.write(&new_name, &content)?;
+        fs::remove_file(&template.path)?;
+      } else {
+        let path = template.path.with_file_name(&new_name);
+
+        let sidecar = Template::sidecar(&template.path);
+
+        if template.kind == TemplateKind::Plain && sidecar.is_file() {
+          fs::rename(sidecar, Template::sidecar(&path))?;
+        }
+
+        fs::rename(&template.path, path)?;
+      }
+
       println!(
         "Renamed template `{}` to `{}` successfully",
         old_name.bold(),
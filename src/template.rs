@@ -2,26 +2,37 @@ use super::*;
 
 #[derive(Debug, Clone)]
 pub(crate) struct Template {
+  pub(crate) binary: Option<Vec<u8>>,
   pub(crate) content: String,
+  pub(crate) kind: TemplateKind,
   pub(crate) path: PathBuf,
-  pub(crate) variables: HashMap<String, Value>,
+  pub(crate) variables: IndexMap<String, Value>,
 }
 
 impl SkimItem for Template {
   fn preview(&self, _context: PreviewContext) -> ItemPreview {
-    ItemPreview::Command(format!("cat \"{}\"", self.path.display()))
+    if self.kind == TemplateKind::Directory {
+      return ItemPreview::Text(
+        self
+          .entries()
+          .unwrap_or_default()
+          .iter()
+          .filter_map(|entry| entry.filename()?.as_str().map(str::to_owned))
+          .collect::<Vec<String>>()
+          .join("\n"),
+      );
+    }
+
+    match &self.binary {
+      Some(bytes) => {
+        ItemPreview::Text(format!("binary, {} bytes", bytes.len()))
+      }
+      None => ItemPreview::Command(format!("cat \"{}\"", self.path.display())),
+    }
   }
 
   fn text(&self) -> Cow<str> {
-    Cow::Owned(
-      self
-        .path
-        .file_stem()
-        .unwrap_or_default()
-        .to_str()
-        .unwrap()
-        .to_string(),
-    )
+    Cow::Owned(self.name().unwrap_or_default())
   }
 }
 
@@ -29,51 +40,238 @@ impl TryFrom<PathBuf> for Template {
   type Error = anyhow::Error;
 
   fn try_from(path: PathBuf) -> Result<Self> {
-    let content = fs::read_to_string(path.clone())?;
+    let kind = if path.is_dir() {
+      TemplateKind::Directory
+    } else if path.to_string_lossy().ends_with(TEMPLATE_EXTENSION) {
+      TemplateKind::Skeleton
+    } else {
+      TemplateKind::Plain
+    };
 
-    if !content.starts_with(Self::FRONTMATTER_DELIMITER) {
-      bail!(
-        "invalid template: {}, template must start with `{}` to specify its frontmatter",
+    let source = if kind == TemplateKind::Directory {
+      path.join(Self::MANIFEST)
+    } else {
+      path.clone()
+    };
+
+    let (binary, content) = Self::read(&source)?;
+
+    match kind {
+      TemplateKind::Skeleton if binary.is_some() => bail!(
+        "invalid template: {}, template must be valid UTF-8, drop the `{}` \
+         extension to copy it byte-for-byte instead",
         path.display(),
-        Self::FRONTMATTER_DELIMITER
-      );
+        TEMPLATE_EXTENSION
+      ),
+      TemplateKind::Directory if binary.is_some() => bail!(
+        "invalid template: {}, manifest must be valid UTF-8",
+        source.display()
+      ),
+      _ => {}
     }
 
-    let frontmatter_end = content
-      .find(&format!("\n{}", Self::FRONTMATTER_DELIMITER))
-      .ok_or_else(|| {
-        anyhow!(
-          "invalid template: {}, template must contain a frontmatter ending with `{}`",
-          path.display(),
-          Self::FRONTMATTER_DELIMITER
-        )
-      })?;
-
-    let frontmatter =
-      &content[Self::FRONTMATTER_DELIMITER.len()..frontmatter_end].trim();
+    let mut template = Template {
+      binary,
+      content,
+      kind,
+      path,
+      variables: IndexMap::new(),
+    };
 
-    let mut variables = HashMap::new();
+    match kind {
+      TemplateKind::Directory => {
+        template.variables = Self::parse_variables(
+          &source,
+          &template.content,
+          &template.content,
+          0,
+        )?;
+
+        if template.filename().unwrap_or_default().is_null() {
+          template.replace_variable("filename", Value::from("."));
+        }
+      }
+      TemplateKind::Plain => {
+        let sidecar = Self::sidecar(&template.path);
+
+        if sidecar.is_file() {
+          let yaml = fs::read_to_string(&sidecar)?;
+
+          template.variables =
+            Self::parse_variables(&sidecar, &yaml, &yaml, 0)?;
+        }
+
+        if template.filename().unwrap_or_default().is_null() {
+          template.replace_variable("filename", Value::from(template.name()?));
+        }
+      }
+      TemplateKind::Entry | TemplateKind::Skeleton => {
+        let frontmatter =
+          Frontmatter::parse(&template.content).map_err(|error| {
+            Diagnostic::locate_error(
+              error,
+              &template.path,
+              &template.content,
+              0,
+            )
+          })?;
+
+        template.variables = Self::parse_variables(
+          &template.path,
+          &template.content,
+          frontmatter.yaml,
+          frontmatter.yaml_offset,
+        )?;
+      }
+    }
 
-    if !frontmatter.is_empty() {
-      variables
-        .extend(serde_yaml::from_str::<HashMap<String, Value>>(frontmatter)?);
+    for name in template.variables.keys() {
+      template.schema(name)?;
     }
 
-    Ok(Template {
-      content,
-      path,
-      variables,
-    })
+    Ok(template)
   }
 }
 
 impl Template {
-  const FRONTMATTER_DELIMITER: &'static str = "---";
+  pub(crate) const EFFECT_VARIABLES: [&'static str; 6] = [
+    "allow_undefined",
+    "command",
+    "delimiters",
+    "filename",
+    "groups",
+    "mode",
+  ];
+
+  pub(crate) const MANIFEST: &'static str = "skeleton.yaml";
+
+  pub(crate) const SIDECAR_EXTENSION: &'static str = ".yaml";
+
+  pub(crate) fn allow_undefined(&self) -> bool {
+    self
+      .variables
+      .get("allow_undefined")
+      .is_some_and(ValueExt::is_truthy)
+  }
+
+  /// Returns the template body, exactly as written, along with its byte offset
+  /// into `content`.
+  pub(crate) fn body(&self) -> Result<(usize, &str)> {
+    match self.kind {
+      TemplateKind::Directory => bail!(
+        "template `{}` is a directory and has no content of its own",
+        self.name()?
+      ),
+      TemplateKind::Entry | TemplateKind::Plain => {
+        return Ok((0, &self.content));
+      }
+      TemplateKind::Skeleton => {}
+    }
+
+    let frontmatter = Frontmatter::parse(&self.content).map_err(|error| {
+      Diagnostic::locate_error(error, &self.path, &self.content, 0)
+    })?;
+
+    if frontmatter.body.trim().is_empty() {
+      bail!(
+        "invalid template: {}, file must contain content",
+        self.path.display()
+      );
+    }
+
+    Ok((frontmatter.offset, frontmatter.body))
+  }
 
   pub(crate) fn command(&self) -> Option<serde_yaml::Value> {
     self.variables.get("command").cloned()
   }
 
+  /// Returns the names of the free variables whose value is a string
+  /// containing a placeholder, which are computed from other variables.
+  pub(crate) fn computed_variables(&self) -> Result<Vec<String>> {
+    let delimiters = self.delimiters()?;
+
+    Ok(
+      self
+        .variables
+        .iter()
+        .filter(|(name, value)| {
+          !Self::EFFECT_VARIABLES.contains(&name.as_str())
+            && value
+              .as_str()
+              .is_some_and(|value| value.contains(&delimiters.open))
+        })
+        .map(|(name, _)| name.clone())
+        .collect(),
+    )
+  }
+
+  pub(crate) fn delimiters(&self) -> Result<Delimiters> {
+    match self.variables.get("delimiters") {
+      None | Some(Value::Null) => Ok(Delimiters::default()),
+      Some(value) => Delimiters::try_from(value).map_err(|error| {
+        anyhow!("invalid template: {}, {error}", self.path.display())
+      }),
+    }
+  }
+
+  /// Returns the files of a directory template, excluding its manifest and
+  /// any `.git` directory, with its variables and their path relative to the
+  /// directory as their `filename`, so that both are rendered when it's
+  /// applied.
+  pub(crate) fn entries(&self) -> Result<Vec<Template>> {
+    let mut entries = Vec::new();
+
+    for entry in WalkDir::new(&self.path)
+      .sort_by_file_name()
+      .into_iter()
+      .filter_entry(|entry| entry.file_name() != ".git")
+    {
+      let entry = entry?;
+
+      if !entry.file_type().is_file()
+        || entry.depth() == 1 && entry.file_name() == Self::MANIFEST
+      {
+        continue;
+      }
+
+      let filename = entry
+        .path()
+        .strip_prefix(&self.path)?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<Cow<str>>>()
+        .join("/");
+
+      let (binary, content) = Self::read(entry.path())?;
+
+      let mut template = Template {
+        binary,
+        content,
+        kind: TemplateKind::Entry,
+        path: entry.into_path(),
+        variables: self.variables.clone(),
+      };
+
+      template.replace_variable("filename", Value::from(filename));
+
+      template.variables.shift_remove("mode");
+
+      #[cfg(unix)]
+      template.replace_variable(
+        "mode",
+        Value::from(format!(
+          "{:o}",
+          fs::metadata(&template.path)?.permissions().mode() & 0o7777
+        )),
+      );
+
+      entries.push(template);
+    }
+
+    Ok(entries)
+  }
+
   pub(crate) fn filename(&self) -> Option<serde_yaml::Value> {
     self.variables.get("filename").cloned()
   }
@@ -88,55 +286,266 @@ impl Template {
       .cloned()
   }
 
+  /// Whether a directory template entry whose path renders to `path` is left
+  /// out, because a component of it renders to nothing, as in
+  /// `{% if ci %}.github{% endif %}/ci.yaml`.
+  pub(crate) fn is_skipped(path: &str) -> bool {
+    path.split('/').any(str::is_empty)
+  }
+
+  /// Returns the line in `content` of a byte offset into the body.
+  pub(crate) fn line(&self, offset: usize) -> usize {
+    let start = self.body().map_or(0, |(start, _)| start);
+
+    self.content[..start + offset].matches('\n').count() + 1
+  }
+
+  /// Returns the file mode set by the `mode` effect variable, written in
+  /// octal as in `mode: 755` or `mode: "0644"`.
+  pub(crate) fn mode(&self) -> Result<Option<u32>> {
+    let digits = match self.variables.get("mode") {
+      None | Some(Value::Null) => return Ok(None),
+      Some(Value::Number(number)) if number.is_u64() => number.to_string(),
+      Some(Value::String(string)) => {
+        string.strip_prefix("0o").unwrap_or(string).to_owned()
+      }
+      Some(_) => String::new(),
+    };
+
+    match u32::from_str_radix(&digits, 8) {
+      Ok(mode) if mode <= 0o7777 => Ok(Some(mode)),
+      _ => bail!(
+        "invalid template: {}, `mode` must be an octal file mode such as `755`",
+        self.path.display()
+      ),
+    }
+  }
+
+  /// Returns the name of the template, its file name without the template
+  /// extension.
   pub(crate) fn name(&self) -> Result<String> {
-    self
+    let name = self
       .path
-      .file_stem()
-      .ok_or_else(|| anyhow!("failed to get template name"))
-      .and_then(|s| {
-        s.to_str()
-          .ok_or_else(|| anyhow!("failed to convert template name"))
-          .map(ToOwned::to_owned)
-      })
+      .file_name()
+      .ok_or_else(|| anyhow!("failed to get template name"))?
+      .to_str()
+      .ok_or_else(|| anyhow!("failed to convert template name"))?;
+
+    Ok(
+      name
+        .strip_suffix(TEMPLATE_EXTENSION)
+        .unwrap_or(name)
+        .to_owned(),
+    )
+  }
+
+  pub(crate) fn nodes(&self) -> Result<Vec<Node>> {
+    if self.binary.is_some() {
+      bail!(
+        "template `{}` is binary and can only be copied as is",
+        self.name()?
+      );
+    }
+
+    let (offset, body) = self.body()?;
+
+    if self.kind == TemplateKind::Plain {
+      return Ok(if body.is_empty() {
+        Vec::new()
+      } else {
+        vec![Node::Text(body.to_owned())]
+      });
+    }
+
+    TemplateParser::parse(body, &self.delimiters()?).map_err(|error| {
+      if error.is::<Diagnostic>() {
+        Diagnostic::locate_error(error, &self.path, &self.content, offset)
+      } else {
+        anyhow!("invalid template: {}, {error}", self.path.display())
+      }
+    })
+  }
+
+  /// Parses the variables in `yaml`, found at byte offset `offset` into the
+  /// `content` of the file at `path`.
+  fn parse_variables(
+    path: &Path,
+    content: &str,
+    yaml: &str,
+    offset: usize,
+  ) -> Result<IndexMap<String, Value>> {
+    if yaml.trim().is_empty() {
+      return Ok(IndexMap::new());
+    }
+
+    serde_yaml::from_str(yaml).map_err(|error| {
+      Diagnostic::yaml(&error, yaml, &Delimiters::declared(yaml))
+        .locate(path, content, offset)
+        .into()
+    })
+  }
+
+  /// Reads the file at `path`, returning its bytes if it isn't valid UTF-8
+  /// and its content otherwise.
+  fn read(path: &Path) -> Result<(Option<Vec<u8>>, String)> {
+    Ok(match String::from_utf8(fs::read(path)?) {
+      Ok(content) => (None, content),
+      Err(error) => (Some(error.into_bytes()), String::new()),
+    })
+  }
+
+  /// Whether the value of the variable `name` refers to `variable`, e.g.
+  /// whether `command` refers to `sk.file`.
+  pub(crate) fn refers_to(&self, name: &str, variable: &str) -> Result<bool> {
+    let Some(source) = self.variables.get(name).and_then(Value::as_str) else {
+      return Ok(false);
+    };
+
+    Ok(
+      Node::references(&TemplateParser::parse(source, &self.delimiters()?)?)
+        .iter()
+        .any(|reference| reference.variable() == variable),
+    )
   }
 
   pub(crate) fn replace_variable(&mut self, variable: &str, value: Value) {
     self.variables.insert(variable.to_owned(), value);
   }
 
-  pub(crate) fn substitute(&self) -> Result<String> {
-    let frontmatter_end = self.content
-      .find(&format!("\n{}", Self::FRONTMATTER_DELIMITER))
-      .ok_or_else(|| {
+  /// Replaces variables declared with a schema by their default, and
+  /// computed variables, free variables whose value is a string containing a
+  /// placeholder, with their rendered value, resolving those that depend on
+  /// each other in dependency order.
+  ///
+  /// Variables named in `resolved` hold final values, such as those an
+  /// including template already resolved, and are left as they are.
+  pub(crate) fn resolve(&mut self, resolved: &[String]) -> Result {
+    let names = self
+      .variables
+      .keys()
+      .filter(|name| !resolved.contains(name))
+      .cloned()
+      .collect::<Vec<String>>();
+
+    for name in names {
+      if let Some(schema) = self.schema(&name)? {
+        match schema.default() {
+          Some(default) => self.replace_variable(&name, default.clone()),
+          None => {
+            self.variables.shift_remove(&name);
+          }
+        }
+      }
+    }
+
+    let delimiters = self.delimiters()?;
+
+    let mut computed = IndexMap::new();
+
+    for name in self.computed_variables()? {
+      if resolved.contains(&name) {
+        continue;
+      }
+
+      let nodes = TemplateParser::parse(
+        self.variables[&name].as_str().unwrap_or_default(),
+        &delimiters,
+      )
+      .map_err(|error| {
         anyhow!(
-          "invalid template: {}, template must contain a frontmatter ending with `{}`",
-          self.path.display(),
-          Self::FRONTMATTER_DELIMITER
+          "invalid template: {}, computed variable `{name}`: {error}",
+          self.path.display()
         )
       })?;
 
-    let content = self.content
-      [frontmatter_end + Self::FRONTMATTER_DELIMITER.len() + 1..]
-      .trim()
-      .to_owned();
+      computed.insert(name, nodes);
+    }
+
+    let mut order = Vec::new();
+
+    for name in computed.keys() {
+      self.visit(name, &computed, &mut Vec::new(), &mut order)?;
+    }
+
+    for name in order {
+      let value = Renderer::new(self).render_variable(name)?;
+      self.replace_variable(name, Value::String(value));
+    }
+
+    Ok(())
+  }
+
+  /// Returns the schema the free variable `name` is declared with, if any.
+  pub(crate) fn schema(&self, name: &str) -> Result<Option<Schema>> {
+    if Self::EFFECT_VARIABLES.contains(&name) {
+      return Ok(None);
+    }
+
+    self.variables.get(name).map_or(Ok(None), |value| {
+      Schema::parse(value).map_err(|error| {
+        anyhow!(
+          "invalid template: {}, variable `{name}`: {error}",
+          self.path.display()
+        )
+      })
+    })
+  }
+
+  /// Returns the path of the sidecar file holding the variables of the plain
+  /// template at `path`, such as `rustfmt.toml.yaml` for `rustfmt.toml`.
+  pub(crate) fn sidecar(path: &Path) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(Self::SIDECAR_EXTENSION);
+    PathBuf::from(sidecar)
+  }
+
+  /// Appends the computed variable `name` to `order` after the computed
+  /// variables it depends on.
+  fn visit<'a>(
+    &self,
+    name: &'a str,
+    computed: &'a IndexMap<String, Vec<Node>>,
+    stack: &mut Vec<&'a str>,
+    order: &mut Vec<&'a str>,
+  ) -> Result {
+    if order.contains(&name) {
+      return Ok(());
+    }
 
-    if content.is_empty() {
+    if let Some(start) = stack.iter().position(|ancestor| *ancestor == name) {
       bail!(
-        "invalid template: {}, file must contain content",
-        self.path.display()
+        "invalid template: {}, computed variable cycle detected: {} -> `{name}`",
+        self.path.display(),
+        stack[start..]
+          .iter()
+          .map(|ancestor| format!("`{ancestor}`"))
+          .collect::<Vec<String>>()
+          .join(" -> ")
       );
     }
 
-    let mut substituted_content = content;
+    stack.push(name);
 
-    for (key, value) in &self.variables {
-      substituted_content = substituted_content.replace(
-        &format!("{{% {key} %}}"),
-        serde_yaml::to_string(value)?.trim(),
-      );
+    for reference in Node::references(&computed[name]) {
+      if let Some((dependency, _)) = computed.get_key_value(&reference.name) {
+        self.visit(dependency, computed, stack, order)?;
+      }
     }
 
-    Ok(substituted_content)
+    stack.pop();
+
+    order.push(name);
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+impl Template {
+  pub(crate) fn substitute(&self) -> Result<String> {
+    let mut template = self.clone();
+    template.resolve(&[])?;
+    Renderer::new(&template).render()
   }
 }
 
@@ -167,11 +576,11 @@ mod tests {
 
     assert_eq!(template.name().unwrap(), "valid");
 
-    assert_eq!(template.substitute().unwrap(), "Hello, world!");
+    assert_eq!(template.substitute().unwrap(), "Hello, world!\n");
 
     assert_eq!(
       template.variables,
-      HashMap::from_iter(vec![(
+      IndexMap::<String, Value>::from_iter(vec![(
         "var".to_owned(),
         Value::String("world!".to_owned())
       )])
@@ -192,12 +601,12 @@ mod tests {
 
     assert_eq!(
       template.substitute().unwrap(),
-      "Place your content here!\n\nHere is a variable interpolation: foo."
+      "Place your content here!\n\nHere is a variable interpolation: foo.\n"
     );
 
     assert_eq!(
       template.variables,
-      HashMap::from_iter(vec![
+      IndexMap::<String, Value>::from_iter(vec![
         ("command".to_owned(), Value::Null),
         ("filename".to_owned(), Value::Null),
         ("groups".to_owned(), Value::Null),
@@ -234,12 +643,12 @@ mod tests {
 
     assert_eq!(
       template.substitute().unwrap(),
-      "Place your content here!\n\nHere is a variable interpolation: bar."
+      "Place your content here!\n\nHere is a variable interpolation: bar.\n"
     );
 
     assert_eq!(
       template.variables,
-      HashMap::from_iter(vec![
+      IndexMap::<String, Value>::from_iter(vec![
         ("command".to_owned(), Value::String("chmod +x".to_owned())),
         ("filename".to_owned(), Value::String("justfile".to_owned())),
         (
@@ -278,7 +687,15 @@ mod tests {
 
     assert_eq!(
       result.unwrap_err().to_string(),
-      format!("invalid template: {}, template must contain a frontmatter ending with `---`", file.display())
+      format!(
+        "template must contain a frontmatter ending with `---`\n  \
+         --> {}:1:1\n  \
+         |\n\
+         1 | ---\n  \
+         | ^^^\n  \
+         = hint: end the frontmatter with a `---` line of its own",
+        file.display()
+      )
     );
   }
 
@@ -306,8 +723,16 @@ mod tests {
 
     assert_eq!(
       result.unwrap_err().to_string(),
-      format!("invalid template: {}, template must start with `---` to specify its frontmatter",
-      file.display())
+      format!(
+        "template must start with `---` to specify its frontmatter\n  \
+         --> {}:1:1\n  \
+         |\n\
+         1 | var: world!\n  \
+         | ^^^^^^^^^^^\n  \
+         = hint: write the frontmatter between two `---` lines at the top of \
+         the template",
+        file.display()
+      )
     );
   }
 
@@ -334,25 +759,1146 @@ mod tests {
     let mut template = Template::try_from(file).unwrap();
 
     assert_eq!(template.name().unwrap(), "replace");
-    assert_eq!(template.substitute().unwrap(), "hello, world!");
+    assert_eq!(template.substitute().unwrap(), "hello, world!\n");
 
     template.replace_variable("greeting", Value::String("welcome".to_owned()));
-    assert_eq!(template.substitute().unwrap(), "welcome, world!");
+    assert_eq!(template.substitute().unwrap(), "welcome, world!\n");
 
     template.replace_variable("name", Value::String("friend".to_owned()));
-    assert_eq!(template.substitute().unwrap(), "welcome, friend!");
+    assert_eq!(template.substitute().unwrap(), "welcome, friend!\n");
 
     template.replace_variable("extra", Value::String("unused".to_owned()));
 
     assert_eq!(
       template.variables,
-      HashMap::from_iter(vec![
+      IndexMap::<String, Value>::from_iter(vec![
         ("greeting".to_owned(), Value::String("welcome".to_owned())),
         ("name".to_owned(), Value::String("friend".to_owned())),
         ("extra".to_owned(), Value::String("unused".to_owned()))
       ])
     );
 
-    assert_eq!(template.substitute().unwrap(), "welcome, friend!");
+    assert_eq!(template.substitute().unwrap(), "welcome, friend!\n");
+  }
+
+  #[test]
+  fn conditionals() {
+    let tempdir = TempDir::new("conditionals").unwrap();
+
+    let file = tempdir.path().join("conditionals.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        coverage: true
+        empty: ''
+        groups: []
+        nothing: null
+        ---
+        {% if coverage %}
+        coverage
+        {% else %}
+        no coverage
+        {% endif %}
+        {% if empty %}empty{% else %}not empty{% endif %}
+        {% if groups %}groups{% else %}no groups{% endif %}
+        {% if nothing %}something{% endif %}
+        {% if missing | default(false) %}missing{% else %}not missing{% endif %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file).unwrap();
+
+    assert_eq!(
+      template.substitute().unwrap(),
+      "coverage\nnot empty\nno groups\n\nnot missing\n"
+    );
+  }
+
+  #[test]
+  fn loops() {
+    let tempdir = TempDir::new("loops").unwrap();
+
+    let file = tempdir.path().join("loops.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        members: [foo, bar]
+        features:
+          default: std
+          std: alloc
+        ---
+        [workspace]
+        members = [
+          {% for member in members %}
+          \"{% member %}\",
+          {% endfor %}
+        ]
+
+        {% for feature in features %}{% feature %},{% endfor %}
+        {% for name, dependencies in features %}
+        {% name %} = [\"{% dependencies %}\"]
+        {% endfor %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file).unwrap();
+
+    assert_eq!(
+      template.substitute().unwrap(),
+      indoc! {"
+        [workspace]
+        members = [
+          \"foo\",
+          \"bar\",
+        ]
+
+        default,std,
+        default = [\"std\"]
+        std = [\"alloc\"]
+      "}
+    );
+  }
+
+  #[test]
+  fn filters() {
+    let tempdir = TempDir::new("filters").unwrap();
+
+    let file = tempdir.path().join("filters.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        name: My Project
+        ---
+        {% name | snake_case %}
+        {% name | kebab_case %}
+        {% name | pascal_case %}
+        {% name | replace(\" \", \"+\") | upper %}
+        {% missing | default(\"fallback\") %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file).unwrap();
+
+    assert_eq!(
+      template.substitute().unwrap(),
+      "my_project\nmy-project\nMyProject\nMY+PROJECT\nfallback\n"
+    );
+  }
+
+  #[test]
+  fn undefined_variables() {
+    let tempdir = TempDir::new("undefined").unwrap();
+
+    let file = tempdir.path().join("undefined.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        name: foo
+        ---
+        {% name %}
+
+        {% nmae %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let mut template = Template::try_from(file.clone()).unwrap();
+
+    assert_eq!(
+      template.substitute().unwrap_err().to_string(),
+      format!(
+        "undefined variable `nmae`\n  \
+         --> {}:6:1\n  \
+         |\n\
+         6 | {{% nmae %}}\n  \
+         | ^^^^^^^^^^\n  \
+         = hint: pass `--allow-undefined` or set `allow_undefined: true` in \
+         the frontmatter to leave it as is",
+        file.display()
+      )
+    );
+
+    template.replace_variable("allow_undefined", Value::Bool(true));
+
+    assert_eq!(template.substitute().unwrap(), "foo\n\n{% nmae %}\n");
+  }
+
+  #[test]
+  fn literal_delimiters() {
+    let tempdir = TempDir::new("literal").unwrap();
+
+    let file = tempdir.path().join("literal.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      r"
+        ---
+        title: Home
+        ---
+        <title>{% title %}</title>
+        \{% title %}
+        {% raw %}
+        {% if page.title %}{{ page.title }}{% endif %}
+        {% endraw %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file).unwrap();
+
+    assert_eq!(
+      template.substitute().unwrap(),
+      "<title>Home</title>\n{% title %}\n{% if page.title %}{{ page.title }}{% endif %}\n"
+    );
+  }
+
+  #[test]
+  fn custom_delimiters() {
+    let tempdir = TempDir::new("delimiters").unwrap();
+
+    let file = tempdir.path().join("delimiters.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        delimiters: [\"<<\", \">>\"]
+        name: site
+        ---
+        {% if page %}<< name >>{% endif %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file).unwrap();
+
+    assert_eq!(
+      template.substitute().unwrap(),
+      "{% if page %}site{% endif %}\n"
+    );
+  }
+
+  #[test]
+  fn nested_variables() {
+    let tempdir = TempDir::new("nested").unwrap();
+
+    let file = tempdir.path().join("nested.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        db:
+          host: localhost
+          port: 5432
+        authors:
+          - name: Alice
+            email: alice@example.com
+        ---
+        {% db.host %}:{% db.port %}
+        {% authors[0].email %}
+        {% for author in authors %}{% author.name | upper %}{% endfor %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file).unwrap();
+
+    assert_eq!(
+      template.substitute().unwrap(),
+      "localhost:5432\nalice@example.com\nALICE\n"
+    );
+  }
+
+  #[test]
+  fn includes() {
+    let tempdir = TempDir::new("includes").unwrap();
+
+    let store = Store::try_from(tempdir.path().to_path_buf()).unwrap();
+
+    store
+      .write(
+        "license",
+        indoc! {"
+          ---
+          author: nobody
+          holder: \"{% author | upper %}\"
+          ---
+          Copyright (c) {% holder %}{% tag %}
+        "},
+      )
+      .unwrap();
+
+    store
+      .write(
+        "main",
+        indoc! {"
+          ---
+          author: Alice
+          tag: \"\\\\{% lit %}\"
+          ---
+          // {% include \"license\" %}
+          fn main() {}
+        "},
+      )
+      .unwrap();
+
+    let mut template = store.template("main").unwrap();
+
+    template.resolve(&[]).unwrap();
+
+    assert_eq!(
+      Renderer::new(&template).store(&store).render().unwrap(),
+      "// Copyright (c) ALICE{% lit %}\n\nfn main() {}\n"
+    );
+  }
+
+  #[test]
+  fn missing_includes() {
+    let tempdir = TempDir::new("includes").unwrap();
+
+    let store = Store::try_from(tempdir.path().to_path_buf()).unwrap();
+
+    store
+      .write(
+        "main",
+        &format!(
+          "---\nfilename: \"out-{}{{% include \\\"nope\\\" %}}\"\n---\n\
+           body\n{{% include \"gone\" %}}\n",
+          "a".repeat(100)
+        ),
+      )
+      .unwrap();
+
+    let template = store.template("main").unwrap();
+
+    assert_eq!(
+      Renderer::new(&template)
+        .store(&store)
+        .render_variable("filename")
+        .unwrap_err()
+        .to_string(),
+      "template `nope` does not exist, referenced from template `main` in \
+       the value of `filename`"
+    );
+
+    assert_eq!(
+      Renderer::new(&template)
+        .store(&store)
+        .render()
+        .unwrap_err()
+        .to_string(),
+      "template `gone` does not exist, referenced from template `main` on \
+       line 5"
+    );
+  }
+
+  #[test]
+  fn include_cycles() {
+    let tempdir = TempDir::new("cycles").unwrap();
+
+    let store = Store::try_from(tempdir.path().to_path_buf()).unwrap();
+
+    for (name, include) in [("a", "b"), ("b", "c"), ("c", "a")] {
+      store
+        .write(name, &format!("---\n---\n{{% include \"{include}\" %}}\n"))
+        .unwrap();
+    }
+
+    assert_eq!(
+      Renderer::new(&store.template("a").unwrap())
+        .store(&store)
+        .render()
+        .unwrap_err()
+        .to_string(),
+      "include cycle detected: `a` -> `b` -> `c` -> `a`"
+    );
+  }
+
+  #[test]
+  fn inheritance() {
+    let tempdir = TempDir::new("inheritance").unwrap();
+
+    let store = Store::try_from(tempdir.path().to_path_buf()).unwrap();
+
+    store
+      .write(
+        "base-ci",
+        indoc! {"
+          ---
+          toolchain: stable
+          ---
+          steps:
+            - uses: {% toolchain %}
+          {% block test %}
+            - run: cargo test
+          {% endblock %}
+          {% block extra %}
+          {% endblock %}
+        "},
+      )
+      .unwrap();
+
+    store
+      .write(
+        "ci",
+        indoc! {"
+          ---
+          filename: ci.yaml
+          toolchain: nightly
+          ---
+          {% extends \"base-ci\" %}
+          {% block test %}
+            - run: cargo +{% toolchain %} test --all
+          {% endblock %}
+        "},
+      )
+      .unwrap();
+
+    store
+      .write(
+        "ci-coverage",
+        indoc! {"
+          ---
+          filename: ci.yaml
+          ---
+          {% extends \"ci\" %}
+          {% block extra %}
+            - run: cargo llvm-cov
+          {% endblock %}
+        "},
+      )
+      .unwrap();
+
+    let render = |name: &str| {
+      Renderer::new(&store.template(name).unwrap())
+        .store(&store)
+        .render()
+        .unwrap()
+    };
+
+    assert_eq!(
+      render("ci"),
+      "steps:\n  - uses: nightly\n  - run: cargo +nightly test --all\n"
+    );
+
+    assert_eq!(
+      render("ci-coverage"),
+      indoc! {"
+        steps:
+          - uses: nightly
+          - run: cargo +nightly test --all
+          - run: cargo llvm-cov
+      "}
+    );
+  }
+
+  #[test]
+  fn builtins() {
+    let tempdir = TempDir::new("builtins").unwrap();
+
+    let file = tempdir.path().join("builtins.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        filename: LICENSE
+        ---
+        {% sk.year %} {% sk.date %}
+        {% env.CARGO_PKG_NAME %}
+        {% if sk.dir_name %}dir{% endif %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file.clone()).unwrap();
+
+    let now = Local::now();
+
+    assert_eq!(
+      template.substitute().unwrap(),
+      format!(
+        "{} {}\nskeleton-cli\ndir\n",
+        now.year(),
+        now.format("%Y-%m-%d")
+      )
+    );
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        sk:
+          year: 1970
+        ---
+        {% sk.year %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file).unwrap();
+
+    assert_eq!(template.substitute().unwrap(), "1970\n");
+  }
+
+  #[test]
+  fn computed_variables() {
+    let tempdir = TempDir::new("computed").unwrap();
+
+    let file = tempdir.path().join("computed.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        name: my-crate
+        module: \"{% crate_ident %}::lib\"
+        crate_ident: \"{% name | snake_case %}\"
+        items: [a, b]
+        list: \"{% for item in items %}{% item %}{% endfor %}\"
+        ---
+        {% crate_ident %} {% module %} {% list %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file.clone()).unwrap();
+
+    let mut computed = template.computed_variables().unwrap();
+
+    computed.sort();
+
+    assert_eq!(computed, ["crate_ident", "list", "module"]);
+
+    assert_eq!(
+      template.substitute().unwrap(),
+      "my_crate my_crate::lib ab\n"
+    );
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        a: \"{% b %}\"
+        b: \"{% c | upper %}\"
+        c: \"{% a %}\"
+        ---
+        {% a %}
+      ",
+      },
+    )
+    .unwrap();
+
+    assert_eq!(
+      Template::try_from(file.clone())
+        .unwrap()
+        .substitute()
+        .unwrap_err()
+        .to_string(),
+      format!(
+        "invalid template: {}, computed variable cycle detected: `a` -> `b` \
+         -> `c` -> `a`",
+        file.display()
+      )
+    );
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        a: \"{% missing %}\"
+        ---
+        {% a %}
+      ",
+      },
+    )
+    .unwrap();
+
+    assert!(Template::try_from(file)
+      .unwrap()
+      .substitute()
+      .unwrap_err()
+      .to_string()
+      .starts_with(
+        "undefined variable `missing` in template `computed` in the value of \
+         `a`"
+      ));
+  }
+
+  #[test]
+  fn filename() {
+    let tempdir = TempDir::new("filename").unwrap();
+
+    let file = tempdir.path().join("filename.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        filename: src/{% module | snake_case %}/mod.rs
+        module: http-client
+        ---
+        mod {% module | snake_case %};
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file.clone()).unwrap();
+
+    assert_eq!(
+      Renderer::new(&template)
+        .render_variable("filename")
+        .unwrap(),
+      "src/http_client/mod.rs"
+    );
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        filename: 5
+        ---
+        content
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file.clone()).unwrap();
+
+    assert_eq!(
+      Renderer::new(&template)
+        .render_variable("filename")
+        .unwrap_err()
+        .to_string(),
+      format!(
+        "invalid template: {}, variable `filename` must be a string",
+        file.display()
+      )
+    );
+  }
+
+  #[test]
+  fn command() {
+    let tempdir = TempDir::new("command").unwrap();
+
+    let file = tempdir.path().join("command.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        command: sed -i 's/{% from %}/b c/' \"{% sk.file %}\"
+        from: a
+        ---
+        a
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file.clone()).unwrap();
+
+    let path = PathBuf::from("/tmp/my project/a.txt");
+
+    assert_eq!(
+      Renderer::new(&template)
+        .file(&path)
+        .render_command()
+        .unwrap(),
+      ["sed", "-i", "s/a/b c/", "/tmp/my project/a.txt"]
+    );
+
+    let path = PathBuf::from("/tmp/\"$HOME\" it's/a.txt");
+
+    let mut template = template;
+
+    template.replace_variable(
+      "command",
+      Value::from(
+        "touch {% msg %} '{% words %}.txt' {% msg | replace(\" \", \"_\") %} \
+         {% sk.file %}",
+      ),
+    );
+
+    template.replace_variable("msg", Value::from("it's done"));
+    template.replace_variable("words", Value::from("two words"));
+
+    assert_eq!(
+      Renderer::new(&template)
+        .file(&path)
+        .render_command()
+        .unwrap(),
+      [
+        "touch",
+        "it's done",
+        "two words.txt",
+        "it's_done",
+        "/tmp/\"$HOME\" it's/a.txt"
+      ]
+    );
+
+    assert!(template.refers_to("command", "sk.file").unwrap());
+
+    assert!(!template.refers_to("command", "from.file").unwrap());
+
+    assert!(!template.refers_to("filename", "sk.file").unwrap());
+
+    template.replace_variable(
+      "command",
+      Value::from("echo \\{% msg %} C:\\\\{% words %} \u{e000}7\u{e000}"),
+    );
+
+    assert_eq!(
+      Renderer::new(&template).render_command().unwrap(),
+      ["echo", "{% msg %}", r"C:\two words", "7"]
+    );
+  }
+
+  #[test]
+  fn schemas() {
+    let tempdir = TempDir::new("schemas").unwrap();
+
+    let file = tempdir.path().join("schemas.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        license:
+          type: choice
+          choices: [MIT, Apache-2.0]
+          default: MIT
+        port: {type: int, default: 8080}
+        name: {type: string}
+        db: {type: postgres}
+        ---
+        {% license %} {% port %} {% db.type %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let mut template = Template::try_from(file.clone()).unwrap();
+
+    assert!(template.schema("license").unwrap().is_some());
+
+    assert!(template.schema("port").unwrap().is_some());
+
+    assert!(template.schema("db").unwrap().is_none());
+
+    assert_eq!(template.substitute().unwrap(), "MIT 8080 postgres\n");
+
+    template.resolve(&[]).unwrap();
+
+    assert_eq!(template.variables["port"], Value::from(8080));
+
+    assert!(!template.variables.contains_key("name"));
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        license: {type: choice, choices: []}
+        ---
+        {% license %}
+      ",
+      },
+    )
+    .unwrap();
+
+    assert_eq!(
+      Template::try_from(file.clone()).unwrap_err().to_string(),
+      format!(
+        "invalid template: {}, variable `license`: `choices` must not be empty",
+        file.display()
+      )
+    );
+  }
+
+  #[test]
+  fn variable_order() {
+    let tempdir = TempDir::new("order").unwrap();
+
+    let file = tempdir.path().join("order.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        zeta: 1
+        filename: out
+        alpha: {type: string, default: a}
+        mu: \"{% alpha %}{% zeta %}\"
+        beta: 2
+        ---
+        {% mu %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let mut template = Template::try_from(file).unwrap();
+
+    template.resolve(&[]).unwrap();
+
+    assert_eq!(
+      template.variables.keys().collect::<Vec<&String>>(),
+      ["zeta", "filename", "alpha", "mu", "beta"]
+    );
+
+    assert_eq!(template.variables["mu"], Value::from("a1"));
+  }
+
+  #[test]
+  fn raw_scalars() {
+    let tempdir = TempDir::new("raw").unwrap();
+
+    let file = tempdir.path().join("raw.skeleton");
+
+    fs::write(
+      &file,
+      indoc! {
+      "
+        ---
+        version: \"1.0\"
+        answer: \"yes\"
+        colon: \": x\"
+        nothing: ~
+        script: |
+          set -e
+          make
+        tags: [a, b]
+        ---
+        {% version %} {% answer %} {% colon %} [{% nothing %}]
+        {% script %}
+        {% tags | join(\", \") %} {% tags | json %}
+        {% tags %}
+      ",
+      },
+    )
+    .unwrap();
+
+    let template = Template::try_from(file.clone()).unwrap();
+
+    assert_eq!(
+      template.substitute().unwrap_err().to_string(),
+      format!(
+        "variable `tags` is a sequence\n   \
+         --> {}:14:1\n   \
+         |\n\
+         14 | {{% tags %}}\n   \
+         | ^^^^^^^^^^\n   \
+         = hint: render it with a filter such as `json`, `yaml`, `toml` or \
+         `join(\", \")`",
+        file.display()
+      )
+    );
+
+    let content = fs::read_to_string(&file).unwrap();
+
+    fs::write(&file, content.replace("\n{% tags %}", "")).unwrap();
+
+    assert_eq!(
+      Template::try_from(file).unwrap().substitute().unwrap(),
+      "1.0 yes : x []\nset -e\nmake\n\na, b [\"a\",\"b\"]\n"
+    );
+  }
+
+  #[test]
+  fn exact_body() {
+    let tempdir = TempDir::new("exact").unwrap();
+
+    let file = tempdir.path().join("exact.skeleton");
+
+    fs::write(
+      &file,
+      "\u{feff}---\r\nheader: |\r\n  ---\r\n  kept\r\ntarget: all\r\n---\r\n\r\n\
+       {% target %}:\r\n\tmake \\\r\n\t  build  \r\n\r\n",
+    )
+    .unwrap();
+
+    let template = Template::try_from(file).unwrap();
+
+    assert_eq!(template.variables["header"], Value::from("---\nkept\n"));
+
+    assert_eq!(
+      template.substitute().unwrap(),
+      "\r\nall:\r\n\tmake \\\r\n\t  build  \r\n\r\n"
+    );
+  }
+
+  #[test]
+  fn yaml_errors() {
+    let tempdir = TempDir::new("yaml").unwrap();
+
+    let file = tempdir.path().join("yaml.skeleton");
+
+    fs::write(&file, "---\nfilename: {% name %}.rs\n---\nbody\n").unwrap();
+
+    let error = Template::try_from(file.clone()).unwrap_err().to_string();
+
+    assert!(error.starts_with(&format!(
+      "found character that cannot start any token\n  --> {}:2:12\n",
+      file.display()
+    )));
+
+    assert!(error.ends_with(
+      "  = hint: quote values containing placeholders, as in \
+       `name: \"{% project %}\"`"
+    ));
+  }
+
+  #[test]
+  fn plain_files() {
+    let tempdir = TempDir::new("plain").unwrap();
+
+    let file = tempdir.path().join("rustfmt.toml");
+
+    fs::write(&file, "max_width = 80\n# {% not a placeholder %}\n").unwrap();
+
+    let template = Template::try_from(file.clone()).unwrap();
+
+    assert_eq!(template.kind, TemplateKind::Plain);
+    assert_eq!(template.name().unwrap(), "rustfmt.toml");
+    assert_eq!(template.filename(), Some(Value::from("rustfmt.toml")));
+
+    assert_eq!(
+      template.substitute().unwrap(),
+      "max_width = 80\n# {% not a placeholder %}\n"
+    );
+
+    fs::write(
+      Template::sidecar(&file),
+      "filename: \"{% dir %}/rustfmt.toml\"\ngroups: [rust]\ndir: .config\n",
+    )
+    .unwrap();
+
+    let mut template = Template::try_from(file.clone()).unwrap();
+
+    template.resolve(&[]).unwrap();
+
+    assert_eq!(
+      Renderer::new(&template)
+        .render_variable("filename")
+        .unwrap(),
+      ".config/rustfmt.toml"
+    );
+
+    assert_eq!(template.groups(), Some(vec![Value::from("rust")]));
+
+    fs::write(Template::sidecar(&file), "filename: [\n").unwrap();
+
+    assert!(Template::try_from(file.clone())
+      .unwrap_err()
+      .to_string()
+      .contains(&format!("--> {}:", Template::sidecar(&file).display())));
+
+    let file = tempdir.path().join(".gitkeep");
+
+    fs::write(&file, "").unwrap();
+
+    assert_eq!(Template::try_from(file).unwrap().substitute().unwrap(), "");
+  }
+
+  #[test]
+  fn binary_files() {
+    let tempdir = TempDir::new("binary").unwrap();
+
+    let file = tempdir.path().join("logo.png");
+
+    let bytes = vec![0x89, b'P', b'N', b'G', 0xff, 0x00, b'{', b'%'];
+
+    fs::write(&file, &bytes).unwrap();
+
+    fs::write(
+      Template::sidecar(&file),
+      "filename: assets/logo.png\ngroups: [web]\nmode: 644\n",
+    )
+    .unwrap();
+
+    let template = Template::try_from(file).unwrap();
+
+    assert_eq!(template.binary, Some(bytes));
+    assert_eq!(template.name().unwrap(), "logo.png");
+    assert_eq!(template.groups(), Some(vec![Value::from("web")]));
+    assert_eq!(template.mode().unwrap(), Some(0o644));
+
+    assert_eq!(
+      template.nodes().unwrap_err().to_string(),
+      "template `logo.png` is binary and can only be copied as is"
+    );
+
+    let file = tempdir.path().join("logo.skeleton");
+
+    fs::write(&file, [0xff, 0xfe]).unwrap();
+
+    assert_eq!(
+      Template::try_from(file.clone()).unwrap_err().to_string(),
+      format!(
+        "invalid template: {}, template must be valid UTF-8, drop the \
+         `.skeleton` extension to copy it byte-for-byte instead",
+        file.display()
+      )
+    );
+  }
+
+  #[test]
+  fn mode() {
+    let tempdir = TempDir::new("mode").unwrap();
+
+    let file = tempdir.path().join("mode.skeleton");
+
+    for (mode, expected) in [
+      ("~", Some(None)),
+      ("755", Some(Some(0o755))),
+      ("\"0644\"", Some(Some(0o644))),
+      ("\"0o700\"", Some(Some(0o700))),
+      ("0o700", None),
+      ("\"rwx\"", None),
+      ("17777", None),
+      ("[7]", None),
+    ] {
+      fs::write(&file, format!("---\nmode: {mode}\n---\nbody\n")).unwrap();
+
+      let template = Template::try_from(file.clone()).unwrap();
+
+      match expected {
+        Some(expected) => assert_eq!(template.mode().unwrap(), expected),
+        None => assert_eq!(
+          template.mode().unwrap_err().to_string(),
+          format!(
+            "invalid template: {}, `mode` must be an octal file mode such as \
+             `755`",
+            file.display()
+          )
+        ),
+      }
+    }
+  }
+
+  #[test]
+  fn directories() {
+    let tempdir = TempDir::new("directories").unwrap();
+
+    let directory = tempdir.path().join("rust-cli");
+
+    fs::create_dir_all(directory.join("src")).unwrap();
+
+    fs::write(
+      directory.join(Template::MANIFEST),
+      "filename: \"{% name %}\"\ngroups: [rust]\nname: demo\nci: false\n\
+       stage: prod\n",
+    )
+    .unwrap();
+
+    fs::create_dir_all(directory.join(".git")).unwrap();
+    fs::create_dir_all(directory.join("{% if ci %}.github{% endif %}"))
+      .unwrap();
+
+    fs::write(directory.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+    fs::write(directory.join(".gitignore"), "target\n").unwrap();
+    fs::write(directory.join("Cargo.toml"), "name = \"{% name %}\"\n").unwrap();
+    fs::write(directory.join("src/{% name %}.rs"), "fn main() {}\n").unwrap();
+    fs::write(directory.join("{% if ci %}ci.yaml{% endif %}"), "").unwrap();
+    fs::write(directory.join("{% if ci %}.github{% endif %}/ci.yaml"), "")
+      .unwrap();
+    fs::write(
+      directory.join("{% stage | replace(\"prod\", \"\") %}.env"),
+      "",
+    )
+    .unwrap();
+    fs::write(directory.join("logo.png"), [0x89, 0xff]).unwrap();
+
+    #[cfg(unix)]
+    fs::set_permissions(
+      directory.join("Cargo.toml"),
+      fs::Permissions::from_mode(0o600),
+    )
+    .unwrap();
+
+    let template = Template::try_from(directory).unwrap();
+
+    assert_eq!(template.kind, TemplateKind::Directory);
+    assert_eq!(template.name().unwrap(), "rust-cli");
+    assert_eq!(template.groups(), Some(vec![Value::from("rust")]));
+
+    assert_eq!(
+      template.nodes().unwrap_err().to_string(),
+      "template `rust-cli` is a directory and has no content of its own"
+    );
+
+    let entries = template.entries().unwrap();
+
+    assert_eq!(
+      entries
+        .iter()
+        .map(|entry| {
+          let path = Renderer::new(entry).render_variable("filename").unwrap();
+          (Template::is_skipped(&path), path)
+        })
+        .collect::<Vec<(bool, String)>>(),
+      [
+        (false, ".gitignore".into()),
+        (false, "Cargo.toml".into()),
+        (false, "logo.png".into()),
+        (false, "src/demo.rs".into()),
+        (true, "/ci.yaml".into()),
+        (true, String::new()),
+        (false, ".env".into()),
+      ]
+    );
+
+    assert!(entries
+      .iter()
+      .all(|entry| entry.kind == TemplateKind::Entry));
+
+    assert_eq!(entries[1].substitute().unwrap(), "name = \"demo\"\n");
+    assert_eq!(entries[2].binary, Some(vec![0x89, 0xff]));
+    assert_eq!(entries[3].substitute().unwrap(), "fn main() {}\n");
+
+    #[cfg(unix)]
+    assert_eq!(entries[1].mode().unwrap(), Some(0o600));
   }
 }
@@ -0,0 +1,15 @@
+/// What a template is made of, which decides where its variables live and
+/// how its body is rendered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TemplateKind {
+  /// A directory with a `skeleton.yaml` manifest holding its variables,
+  /// applied by rendering every other file in it.
+  Directory,
+  /// A file within a directory template, whose whole content is its body.
+  Entry,
+  /// A file without frontmatter, written verbatim, with its variables in an
+  /// optional sidecar file.
+  Plain,
+  /// A `.skeleton` file, with its variables in a frontmatter.
+  Skeleton,
+}
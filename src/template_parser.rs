@@ -0,0 +1,714 @@
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'src> {
+  Tag {
+    content: &'src str,
+    offset: usize,
+    source: &'src str,
+  },
+  Text(&'src str),
+}
+
+impl<'src> Token<'src> {
+  fn keyword(self) -> Option<&'src str> {
+    match self {
+      Token::Tag { content, .. } => content.split_whitespace().next(),
+      Token::Text(_) => None,
+    }
+  }
+
+  fn len(self) -> usize {
+    match self {
+      Token::Tag { source, .. } => source.len(),
+      Token::Text(text) => text.len(),
+    }
+  }
+}
+
+/// Parses a template body into a tree of nodes.
+///
+/// Tags are written as `{% ... %}`, or between the template's own
+/// `delimiters`. Block tags (all tags except placeholders and includes) that
+/// sit alone on a line are standalone, meaning the whole line, including its
+/// newline, is removed from the output.
+///
+/// A backslash directly before an opening delimiter escapes it, while a
+/// doubled one writes a single backslash before the tag. Everything between
+/// `{% raw %}` and `{% endraw %}` is emitted verbatim.
+pub(crate) struct TemplateParser<'src> {
+  delimiters: &'src Delimiters,
+  position: usize,
+  tokens: Vec<Token<'src>>,
+}
+
+impl<'src> TemplateParser<'src> {
+  const BLOCK_KEYWORDS: [&'static str; 10] = [
+    "block", "else", "endblock", "endfor", "endif", "endraw", "extends", "for",
+    "if", "raw",
+  ];
+  const ESCAPE: char = '\\';
+
+  fn is_identifier(name: &str) -> bool {
+    name
+      .chars()
+      .next()
+      .is_some_and(|c| c.is_alphabetic() || c == '_')
+      && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+  }
+
+  fn is_standalone(tokens: &[Token], index: usize) -> bool {
+    let before = match index.checked_sub(1).map(|i| tokens[i]) {
+      None => true,
+      Some(Token::Text(text)) => {
+        let (line_start, tail) = match text.rfind('\n') {
+          Some(newline) => (true, &text[newline + 1..]),
+          None => (index == 1, text),
+        };
+
+        line_start && tail.trim().is_empty()
+      }
+      Some(Token::Tag { .. }) => false,
+    };
+
+    let after = match tokens.get(index + 1) {
+      None => true,
+      Some(Token::Text(text)) => {
+        let (line_end, head) = match text.find('\n') {
+          Some(newline) => (true, &text[..newline]),
+          None => (index + 2 == tokens.len(), *text),
+        };
+
+        line_end && head.trim().is_empty()
+      }
+      Some(Token::Tag { .. }) => false,
+    };
+
+    before && after
+  }
+
+  pub(crate) fn parse(
+    src: &'src str,
+    delimiters: &'src Delimiters,
+  ) -> Result<Vec<Node>> {
+    let mut parser = Self {
+      delimiters,
+      position: 0,
+      tokens: Vec::new(),
+    };
+
+    parser.tokens = parser.tokenize(src)?;
+
+    let (nodes, _) = parser.parse_nodes(&[])?;
+
+    Ok(nodes)
+  }
+
+  fn parse_block(&mut self, content: &str, offset: usize) -> Result<Node> {
+    let name = content["block".len()..].trim();
+
+    if !Self::is_identifier(name) {
+      bail!(
+        "invalid block `{}`, expected `{}`",
+        self.delimiters.tag(content),
+        self.delimiters.tag("block name")
+      );
+    }
+
+    let (body, terminator) = self.parse_nodes(&["endblock"])?;
+
+    if terminator.is_none() {
+      bail!(
+        "missing `{}` for `{}`",
+        self.delimiters.tag("endblock"),
+        self.delimiters.tag(content)
+      );
+    }
+
+    Ok(Node::Block {
+      body,
+      name: name.to_owned(),
+      offset,
+    })
+  }
+
+  fn parse_conditional(
+    &mut self,
+    content: &str,
+    offset: usize,
+  ) -> Result<Node> {
+    let condition = content["if".len()..].trim();
+
+    if condition.is_empty() {
+      bail!("`{}` requires a condition", self.delimiters.tag("if"));
+    }
+
+    let (consequent, terminator) = self.parse_nodes(&["else", "endif"])?;
+
+    let missing = || {
+      anyhow!(
+        "missing `{}` for `{}`",
+        self.delimiters.tag("endif"),
+        self.delimiters.tag(content)
+      )
+    };
+
+    let alternative = match terminator {
+      Some("else") => match self.parse_nodes(&["endif"])? {
+        (alternative, Some(_)) => alternative,
+        (_, None) => return Err(missing()),
+      },
+      Some(_) => Vec::new(),
+      None => return Err(missing()),
+    };
+
+    Ok(Node::Conditional {
+      alternative,
+      condition: condition.parse()?,
+      consequent,
+      offset,
+    })
+  }
+
+  fn parse_loop(&mut self, content: &str, offset: usize) -> Result<Node> {
+    let invalid = || {
+      anyhow!(
+        "invalid loop `{}`, expected `{}` or `{}`",
+        self.delimiters.tag(content),
+        self.delimiters.tag("for item in list"),
+        self.delimiters.tag("for key, value in mapping")
+      )
+    };
+
+    let (bindings, iterable) = content["for".len()..]
+      .split_once(" in ")
+      .ok_or_else(invalid)?;
+
+    let bindings = bindings.split(',').map(str::trim).collect::<Vec<&str>>();
+
+    let iterable = iterable.trim();
+
+    if iterable.is_empty()
+      || bindings.iter().any(|binding| !Self::is_identifier(binding))
+    {
+      return Err(invalid());
+    }
+
+    let (key, value) = match bindings.as_slice() {
+      [value] => (None, value.to_string()),
+      [key, value] => (Some(key.to_string()), value.to_string()),
+      _ => return Err(invalid()),
+    };
+
+    let (body, terminator) = self.parse_nodes(&["endfor"])?;
+
+    if terminator.is_none() {
+      bail!(
+        "missing `{}` for `{}`",
+        self.delimiters.tag("endfor"),
+        self.delimiters.tag(content)
+      );
+    }
+
+    Ok(Node::Loop {
+      body,
+      iterable: iterable.parse()?,
+      key,
+      offset,
+      value,
+    })
+  }
+
+  fn parse_nodes(
+    &mut self,
+    terminators: &[&str],
+  ) -> Result<(Vec<Node>, Option<&'src str>)> {
+    let mut nodes = Vec::new();
+
+    while let Some(token) = self.tokens.get(self.position).copied() {
+      self.position += 1;
+
+      let (content, offset, source) = match token {
+        Token::Text(text) => {
+          match nodes.last_mut() {
+            Some(Node::Text(previous)) => previous.push_str(text),
+            _ if !text.is_empty() => nodes.push(Node::Text(text.to_owned())),
+            _ => {}
+          }
+
+          continue;
+        }
+        Token::Tag {
+          content,
+          offset,
+          source,
+        } => (content, offset, source),
+      };
+
+      let keyword = token.keyword().unwrap_or_default();
+
+      if terminators.contains(&keyword) {
+        return Ok((nodes, Some(keyword)));
+      }
+
+      match self.parse_tag(keyword, content, offset, source) {
+        Ok(node) => nodes.extend(node),
+        Err(error) if error.is::<Diagnostic>() => return Err(error),
+        Err(error) => {
+          return Err(
+            Diagnostic::new(error.to_string(), offset, source.len()).into(),
+          );
+        }
+      }
+    }
+
+    Ok((nodes, None))
+  }
+
+  /// Parses the quoted template name following `keyword` in `include` and
+  /// `extends` tags.
+  fn parse_reference(&self, keyword: &str, content: &str) -> Result<String> {
+    let argument = content[keyword.len()..].trim();
+
+    match Expression::parse_argument(argument) {
+      Ok(Value::String(name))
+        if !name.is_empty() && argument.starts_with(['"', '\'']) =>
+      {
+        Ok(name)
+      }
+      _ => bail!(
+        "invalid {keyword} `{}`, expected `{}`",
+        self.delimiters.tag(content),
+        self.delimiters.tag(&format!("{keyword} \"template\""))
+      ),
+    }
+  }
+
+  /// Parses the tag `source` at `offset`, with keyword `keyword` and the text
+  /// between its delimiters `content`, into a node, if it produces one.
+  fn parse_tag(
+    &mut self,
+    keyword: &str,
+    content: &str,
+    offset: usize,
+    source: &str,
+  ) -> Result<Option<Node>> {
+    let node = match keyword {
+      "block" => self.parse_block(content, offset)?,
+      "extends" => Node::Extends {
+        name: self.parse_reference("extends", content)?,
+        offset,
+      },
+      "for" => self.parse_loop(content, offset)?,
+      "if" => self.parse_conditional(content, offset)?,
+      "include" => Node::Include {
+        name: self.parse_reference("include", content)?,
+        offset,
+      },
+      "raw" if content == "raw" => {
+        let text = match self.tokens.get(self.position) {
+          Some(Token::Text(text)) => Some(Node::Text((*text).to_owned())),
+          _ => None,
+        };
+
+        self.position += 2;
+
+        return Ok(text);
+      }
+      "else" | "endblock" | "endfor" | "endif" | "endraw" => {
+        return Err(
+          Diagnostic::new(
+            format!("unexpected `{}`", self.delimiters.tag(keyword)),
+            offset,
+            source.len(),
+          )
+          .hint(format!(
+            "escape it as `{}{source}` to write it literally",
+            Self::ESCAPE
+          ))
+          .into(),
+        );
+      }
+      _ => Node::Variable {
+        expression: content.parse()?,
+        offset,
+        source: source.to_owned(),
+      },
+    };
+
+    Ok(Some(node))
+  }
+
+  /// Reads the tag starting at `start`, if it is closed.
+  fn tag(&self, src: &'src str, start: usize) -> Option<Token<'src>> {
+    let Delimiters { close, open } = self.delimiters;
+
+    let end = start + src[start..].find(close.as_str())? + close.len();
+
+    Some(Token::Tag {
+      content: src[start + open.len()..end - close.len()].trim(),
+      offset: start,
+      source: &src[start..end],
+    })
+  }
+
+  fn tokenize(&self, src: &'src str) -> Result<Vec<Token<'src>>> {
+    let open = self.delimiters.open.as_str();
+
+    let mut tokens = Vec::new();
+
+    let mut text_start = 0;
+
+    let mut cursor = 0;
+
+    while let Some(start) = src[cursor..].find(open).map(|i| cursor + i) {
+      let escapes = src[..start]
+        .chars()
+        .rev()
+        .take_while(|c| *c == Self::ESCAPE)
+        .count();
+
+      // An odd number of backslashes escapes the tag, while an even one
+      // writes a backslash before it, as in `C:\Users\\{% user %}`, with the
+      // last backslash removed either way.
+      let doubled = escapes > 0 && escapes % 2 == 0;
+
+      if escapes % 2 == 1 {
+        tokens.push(Token::Text(
+          &src[text_start..start - Self::ESCAPE.len_utf8()],
+        ));
+        text_start = start;
+        cursor = start + open.len();
+        continue;
+      }
+
+      let Some(tag) = self.tag(src, start) else {
+        break;
+      };
+
+      let text_end = if doubled {
+        start - Self::ESCAPE.len_utf8()
+      } else {
+        start
+      };
+
+      tokens.push(Token::Text(&src[text_start..text_end]));
+      tokens.push(tag);
+
+      cursor = start + tag.len();
+
+      if let Token::Tag { content: "raw", .. } = tag {
+        let (offset, endraw) = src[cursor..]
+          .match_indices(open)
+          .filter_map(|(i, _)| Some((cursor + i, self.tag(src, cursor + i)?)))
+          .find(|(_, tag)| {
+            matches!(
+              tag,
+              Token::Tag {
+                content: "endraw",
+                ..
+              }
+            )
+          })
+          .ok_or_else(|| {
+            Diagnostic::new(
+              format!(
+                "missing `{}` for `{}`",
+                self.delimiters.tag("endraw"),
+                self.delimiters.tag("raw")
+              ),
+              start,
+              tag.len(),
+            )
+          })?;
+
+        tokens.push(Token::Text(&src[cursor..offset]));
+        tokens.push(endraw);
+
+        cursor = offset + endraw.len();
+      }
+
+      text_start = cursor;
+    }
+
+    tokens.push(Token::Text(&src[text_start..]));
+
+    Ok(Self::trim_standalone(tokens))
+  }
+
+  fn trim_standalone(mut tokens: Vec<Token<'src>>) -> Vec<Token<'src>> {
+    let standalone = (0..tokens.len())
+      .filter(|&index| {
+        tokens[index]
+          .keyword()
+          .is_some_and(|keyword| Self::BLOCK_KEYWORDS.contains(&keyword))
+          && Self::is_standalone(&tokens, index)
+      })
+      .collect::<Vec<usize>>();
+
+    for index in standalone {
+      if let Some(Token::Text(text)) =
+        index.checked_sub(1).map(|i| &mut tokens[i])
+      {
+        *text = &text[..text.rfind('\n').map_or(0, |newline| newline + 1)];
+      }
+
+      if let Some(Token::Text(text)) = tokens.get_mut(index + 1) {
+        *text =
+          &text[text.find('\n').map_or(text.len(), |newline| newline + 1)..];
+      }
+    }
+
+    tokens
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(src: &str) -> Result<Vec<Node>> {
+    TemplateParser::parse(src, &Delimiters::default())
+  }
+
+  fn text(text: &str) -> Node {
+    Node::Text(text.to_owned())
+  }
+
+  fn variable(name: &str, offset: usize) -> Node {
+    Node::Variable {
+      expression: name.parse().unwrap(),
+      offset,
+      source: format!("{{% {name} %}}"),
+    }
+  }
+
+  #[test]
+  fn text_and_variables() {
+    assert_eq!(
+      parse("Hello, {% name %}!").unwrap(),
+      vec![text("Hello, "), variable("name", 7), text("!")]
+    );
+  }
+
+  #[test]
+  fn unterminated_tag_is_text() {
+    assert_eq!(parse("a {% b").unwrap(), vec![text("a {% b")]);
+  }
+
+  #[test]
+  fn conditional() {
+    assert_eq!(
+      parse("{% if a %}yes{% else %}no{% endif %}").unwrap(),
+      vec![Node::Conditional {
+        alternative: vec![text("no")],
+        condition: "a".parse().unwrap(),
+        consequent: vec![text("yes")],
+        offset: 0,
+      }]
+    );
+  }
+
+  #[test]
+  fn standalone_block_tags_remove_their_lines() {
+    assert_eq!(
+      parse("a\n  {% if b %}\nc\n{% endif %}\nd\n").unwrap(),
+      vec![
+        text("a\n"),
+        Node::Conditional {
+          alternative: Vec::new(),
+          condition: "b".parse().unwrap(),
+          consequent: vec![text("c\n")],
+          offset: 4,
+        },
+        text("d\n"),
+      ]
+    );
+  }
+
+  #[test]
+  fn unbalanced_conditionals() {
+    assert_eq!(
+      parse("{% if a %}b").unwrap_err().to_string(),
+      "missing `{% endif %}` for `{% if a %}`"
+    );
+
+    assert_eq!(
+      parse("a{% endif %}").unwrap_err().to_string(),
+      "unexpected `{% endif %}`\n = hint: escape it as `\\{% endif %}` to \
+       write it literally"
+    );
+
+    assert_eq!(
+      parse("a\n{% if a %}{% if b %}{% endif %}")
+        .unwrap_err()
+        .downcast::<Diagnostic>()
+        .unwrap(),
+      Diagnostic::new("missing `{% endif %}` for `{% if a %}`", 2, 10)
+    );
+  }
+
+  #[test]
+  fn loops() {
+    assert_eq!(
+      parse("{% for item in list %}- {% item %}\n{% endfor %}").unwrap(),
+      vec![Node::Loop {
+        body: vec![text("- "), variable("item", 24), text("\n")],
+        iterable: "list".parse().unwrap(),
+        key: None,
+        offset: 0,
+        value: "item".to_owned(),
+      }]
+    );
+
+    assert_eq!(
+      parse("{% for k, v in map %}{% endfor %}").unwrap(),
+      vec![Node::Loop {
+        body: Vec::new(),
+        iterable: "map".parse().unwrap(),
+        key: Some("k".to_owned()),
+        offset: 0,
+        value: "v".to_owned(),
+      }]
+    );
+  }
+
+  #[test]
+  fn invalid_loops() {
+    assert_eq!(
+      parse("{% for item in list %}").unwrap_err().to_string(),
+      "missing `{% endfor %}` for `{% for item in list %}`"
+    );
+
+    assert_eq!(
+      parse("{% for a b in list %}{% endfor %}")
+        .unwrap_err()
+        .to_string(),
+      "invalid loop `{% for a b in list %}`, expected `{% for item in list %}` \
+       or `{% for key, value in mapping %}`"
+    );
+  }
+
+  #[test]
+  fn escaped_tags() {
+    assert_eq!(
+      parse(r"\{% a %} {% b %}").unwrap(),
+      vec![text("{% a %} "), variable("b", 9)]
+    );
+
+    assert_eq!(
+      parse(r"C:\Users\\{% user %}").unwrap(),
+      vec![text(r"C:\Users\"), variable("user", 10)]
+    );
+
+    assert_eq!(parse(r"\\\{% a %}").unwrap(), vec![text(r"\\{% a %}")]);
+  }
+
+  #[test]
+  fn raw_blocks() {
+    assert_eq!(
+      parse(
+        "{% raw %}\n{% if page %}{{ page.title }}{% endif %}\n{% endraw %}\n{% a %}")
+      .unwrap(),
+      vec![
+        text("{% if page %}{{ page.title }}{% endif %}\n"),
+        variable("a", 64),
+      ]
+    );
+
+    assert_eq!(
+      parse("{% raw %}{% a %}").unwrap_err().to_string(),
+      "missing `{% endraw %}` for `{% raw %}`"
+    );
+
+    assert_eq!(
+      parse("{% endraw %}").unwrap_err().to_string(),
+      "unexpected `{% endraw %}`\n = hint: escape it as `\\{% endraw %}` to \
+       write it literally"
+    );
+  }
+
+  #[test]
+  fn custom_delimiters() {
+    let delimiters = Delimiters {
+      close: ">>".to_owned(),
+      open: "<<".to_owned(),
+    };
+
+    assert_eq!(
+      TemplateParser::parse("{% a %} << b >> \\<< c >>", &delimiters).unwrap(),
+      vec![
+        text("{% a %} "),
+        Node::Variable {
+          expression: "b".parse().unwrap(),
+          offset: 8,
+          source: "<< b >>".to_owned(),
+        },
+        text(" << c >>"),
+      ]
+    );
+
+    assert_eq!(
+      TemplateParser::parse("<< if a >>", &delimiters)
+        .unwrap_err()
+        .to_string(),
+      "missing `<< endif >>` for `<< if a >>`"
+    );
+
+    assert_eq!(
+      TemplateParser::parse("<< raw >> << a >>", &delimiters)
+        .unwrap_err()
+        .to_string(),
+      "missing `<< endraw >>` for `<< raw >>`"
+    );
+  }
+
+  #[test]
+  fn includes() {
+    assert_eq!(
+      parse("a {% include \"license\" %}").unwrap(),
+      vec![
+        text("a "),
+        Node::Include {
+          name: "license".to_owned(),
+          offset: 2,
+        }
+      ]
+    );
+
+    assert_eq!(
+      parse("{% include license %}").unwrap_err().to_string(),
+      "invalid include `{% include license %}`, expected \
+       `{% include \"template\" %}`"
+    );
+  }
+
+  #[test]
+  fn inheritance() {
+    assert_eq!(
+      parse("{% extends \"base\" %}\n{% block steps %}\n- a\n{% endblock %}\n")
+        .unwrap(),
+      vec![
+        Node::Extends {
+          name: "base".to_owned(),
+          offset: 0,
+        },
+        Node::Block {
+          body: vec![text("- a\n")],
+          name: "steps".to_owned(),
+          offset: 21,
+        },
+      ]
+    );
+
+    assert_eq!(
+      parse("{% block %}{% endblock %}").unwrap_err().to_string(),
+      "invalid block `{% block %}`, expected `{% block name %}`"
+    );
+
+    assert_eq!(
+      parse("{% block a %}").unwrap_err().to_string(),
+      "missing `{% endblock %}` for `{% block a %}`"
+    );
+  }
+}
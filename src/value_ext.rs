@@ -0,0 +1,201 @@
+use super::*;
+
+pub(crate) trait ValueExt {
+  /// Parses `answer` as a value of the same type as this one, or as YAML if
+  /// this value doesn't have a scalar type, such as `null`.
+  fn coerce(&self, answer: &str) -> Result<Value>;
+
+  fn get_path(&self, path: &[Segment]) -> Option<&Value>;
+
+  fn is_truthy(&self) -> bool;
+
+  /// Returns the paths to every scalar, or empty mapping or sequence, nested
+  /// within this value.
+  fn leaves(&self) -> Vec<Vec<Segment>>;
+
+  fn scalar(&self) -> Option<String>;
+
+  fn set_path(&mut self, path: &[Segment], value: Value) -> Result;
+}
+
+impl ValueExt for Value {
+  fn coerce(&self, answer: &str) -> Result<Value> {
+    match self {
+      Value::Bool(_) => match answer.trim().to_lowercase().as_str() {
+        "true" | "yes" | "y" => Ok(Value::Bool(true)),
+        "false" | "no" | "n" => Ok(Value::Bool(false)),
+        _ => bail!("expected `true` or `false`"),
+      },
+      Value::Number(number) if number.is_f64() => answer
+        .trim()
+        .parse::<f64>()
+        .map(Value::from)
+        .map_err(|_| anyhow!("expected a number")),
+      Value::Number(_) => answer
+        .trim()
+        .parse::<i64>()
+        .map(Value::from)
+        .map_err(|_| anyhow!("expected an integer")),
+      Value::String(_) => Ok(Value::String(answer.to_owned())),
+      Value::Tagged(tagged) => tagged.value.coerce(answer),
+      Value::Mapping(_) | Value::Null | Value::Sequence(_) => Ok(
+        serde_yaml::from_str::<Value>(answer)
+          .ok()
+          .filter(|value| !value.is_mapping() && !value.is_null())
+          .unwrap_or_else(|| Value::String(answer.to_owned())),
+      ),
+    }
+  }
+
+  fn get_path(&self, path: &[Segment]) -> Option<&Value> {
+    path
+      .iter()
+      .try_fold(self, |value, segment| match (value, segment) {
+        (Value::Mapping(mapping), Segment::Key(key)) => {
+          mapping.get(key.as_str())
+        }
+        (Value::Sequence(sequence), Segment::Index(index)) => {
+          sequence.get(*index)
+        }
+        _ => None,
+      })
+  }
+
+  fn is_truthy(&self) -> bool {
+    match self {
+      Value::Bool(value) => *value,
+      Value::Mapping(mapping) => !mapping.is_empty(),
+      Value::Null => false,
+      Value::Number(number) => number.as_f64().is_some_and(|n| n != 0.0),
+      Value::Sequence(sequence) => !sequence.is_empty(),
+      Value::String(string) => !string.is_empty(),
+      Value::Tagged(tagged) => tagged.value.is_truthy(),
+    }
+  }
+
+  fn leaves(&self) -> Vec<Vec<Segment>> {
+    let children = match self {
+      Value::Mapping(mapping) => mapping
+        .iter()
+        .filter_map(|(key, value)| Some((Segment::Key(key.scalar()?), value)))
+        .collect::<Vec<(Segment, &Value)>>(),
+      Value::Sequence(sequence) => sequence
+        .iter()
+        .enumerate()
+        .map(|(index, value)| (Segment::Index(index), value))
+        .collect(),
+      _ => Vec::new(),
+    };
+
+    if children.is_empty() {
+      return vec![Vec::new()];
+    }
+
+    children
+      .into_iter()
+      .flat_map(|(segment, value)| {
+        value.leaves().into_iter().map(move |mut path| {
+          path.insert(0, segment.clone());
+          path
+        })
+      })
+      .collect()
+  }
+
+  fn scalar(&self) -> Option<String> {
+    match self {
+      Value::Bool(value) => Some(value.to_string()),
+      Value::Number(number) => Some(number.to_string()),
+      Value::String(string) => Some(string.clone()),
+      Value::Tagged(tagged) => tagged.value.scalar(),
+      Value::Mapping(_) | Value::Null | Value::Sequence(_) => None,
+    }
+  }
+
+  fn set_path(&mut self, path: &[Segment], value: Value) -> Result {
+    let Some((segment, rest)) = path.split_first() else {
+      *self = value;
+      return Ok(());
+    };
+
+    let child = match (self, segment) {
+      (Value::Mapping(mapping), Segment::Key(key)) => mapping
+        .entry(Value::String(key.clone()))
+        .or_insert(Value::Null),
+      (Value::Sequence(sequence), Segment::Index(index)) => sequence
+        .get_mut(*index)
+        .ok_or_else(|| anyhow!("index {index} is out of bounds"))?,
+      (_, segment) => bail!("cannot set `{segment}` on a scalar value"),
+    };
+
+    child.set_path(rest, value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn paths() {
+    let mut value = serde_yaml::from_str::<Value>(indoc! {"
+      db:
+        host: localhost
+        ports: [1, 2]
+      tags: []
+    "})
+    .unwrap();
+
+    let leaves = value.leaves();
+
+    assert_eq!(
+      leaves
+        .iter()
+        .map(|path| Segment::display("root", path))
+        .collect::<Vec<String>>(),
+      [
+        "root.db.host",
+        "root.db.ports[0]",
+        "root.db.ports[1]",
+        "root.tags"
+      ]
+    );
+
+    assert_eq!(value.get_path(&leaves[2]), Some(&Value::from(2)));
+
+    value
+      .set_path(&leaves[0], Value::from("example.com"))
+      .unwrap();
+
+    assert_eq!(
+      value.get_path(&leaves[0]),
+      Some(&Value::from("example.com"))
+    );
+  }
+
+  #[test]
+  fn coerce() {
+    for (value, answer, expected) in [
+      (Value::Bool(false), "yes", Value::Bool(true)),
+      (Value::from(2), " 4 ", Value::from(4)),
+      (Value::from(0.5), "1.5", Value::from(1.5)),
+      (Value::from("a"), "true", Value::from("true")),
+      (Value::Null, "true", Value::Bool(true)),
+      (Value::Null, "4", Value::from(4)),
+      (Value::Null, "a: b", Value::from("a: b")),
+      (Value::Null, "", Value::from("")),
+    ] {
+      assert_eq!(value.coerce(answer).unwrap(), expected);
+    }
+
+    assert_eq!(
+      Value::from(2).coerce("two").unwrap_err().to_string(),
+      "expected an integer"
+    );
+
+    assert_eq!(
+      Value::Bool(true).coerce("maybe").unwrap_err().to_string(),
+      "expected `true` or `false`"
+    );
+  }
+}